@@ -0,0 +1,174 @@
+//! Utilities for working with text that contains ANSI escape sequences,
+//! e.g. the output of a `ColorDisplay` impl.
+
+use ansi_term::Style;
+use std::fmt::Write;
+use unicode_width::UnicodeWidthChar;
+
+/// A piece of text that is either an ANSI escape sequence,
+/// or a run of visible text without any escape sequences in it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Segment<'t> {
+    Escape(&'t str),
+    Text(&'t str),
+}
+
+/// Split `text` into escape sequences and runs of visible text.
+pub fn segments(text: &str) -> impl Iterator<Item = Segment<'_>> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let len = if let Some(params) = rest.strip_prefix("\x1b[") {
+            // A CSI sequence ends with a byte in the range 0x40..=0x7e.
+            let end = params.bytes().position(|b| (0x40..=0x7e).contains(&b));
+            end.map(|end| 2 + end + 1).unwrap_or(rest.len())
+        } else {
+            rest.find("\x1b[").filter(|&idx| idx > 0).unwrap_or(rest.len())
+        };
+        let (segment, tail) = rest.split_at(len);
+        rest = tail;
+        if segment.starts_with("\x1b[") {
+            Some(Segment::Escape(segment))
+        } else {
+            Some(Segment::Text(segment))
+        }
+    })
+}
+
+/// Remove all ANSI escape sequences from `text`.
+pub fn strip_escapes(text: &str) -> String {
+    segments(text)
+        .filter_map(|segment| match segment {
+            Segment::Text(text) => Some(text),
+            Segment::Escape(_) => None,
+        })
+        .collect()
+}
+
+/// The width of `text` on a terminal in display columns, ignoring ANSI
+/// escape sequences.  Wide characters, e.g. CJK characters and most emoji,
+/// take up 2 columns.
+pub fn visible_width(text: &str) -> usize {
+    segments(text)
+        .map(|segment| match segment {
+            Segment::Text(text) => text.chars().map(char_width).sum(),
+            Segment::Escape(_) => 0,
+        })
+        .sum()
+}
+
+/// The width of `c` on a terminal in display columns.
+/// Control characters take up no columns.
+pub fn char_width(c: char) -> usize {
+    UnicodeWidthChar::width(c).unwrap_or(0)
+}
+
+/// Shorten `text` to at most `width` display columns, ending in
+/// `ellipsis` if anything was cut off. Escape sequences are preserved, and
+/// a reset is appended if the text was cut while a style was active.  An
+/// ellipsis wider than `width` is itself shortened to `width`.
+pub fn truncate(text: &str, width: usize, ellipsis: &str) -> String {
+    if visible_width(text) <= width {
+        return text.to_string();
+    }
+    if visible_width(ellipsis) > width {
+        return truncate(ellipsis, width, "");
+    }
+    let mut budget = width.saturating_sub(visible_width(ellipsis));
+    let mut truncated = String::with_capacity(text.len());
+    let mut styled = false;
+    for segment in segments(text) {
+        match segment {
+            Segment::Escape(escape) => {
+                styled = !is_reset(escape);
+                truncated.push_str(escape);
+            },
+            Segment::Text(text) => for c in text.chars() {
+                if char_width(c) > budget {
+                    budget = 0;
+                    break;
+                }
+                truncated.push(c);
+                budget -= char_width(c);
+            },
+        }
+        if budget == 0 {
+            break;
+        }
+    }
+    truncated.push_str(ellipsis);
+    if styled {
+        truncated.push_str(RESET);
+    }
+    truncated
+}
+
+/// Paint `text` with `style`, layering it over any styles already present in
+/// `text`: the style is re-applied after every reset in `text`.
+pub fn layer(style: Style, text: &str) -> String {
+    if style == Style::default() {
+        return text.to_string();
+    }
+    let prefix = style.prefix().to_string();
+    let mut layered = String::with_capacity(text.len() + 2 * prefix.len());
+    layered.push_str(&prefix);
+    for segment in segments(text) {
+        match segment {
+            Segment::Escape(escape) => {
+                layered.push_str(escape);
+                if is_reset(escape) {
+                    layered.push_str(&prefix);
+                }
+            },
+            Segment::Text(text) => layered.push_str(text),
+        }
+    }
+    let _ = write!(layered, "{}", style.suffix());
+    layered
+}
+
+pub(crate) const RESET: &str = "\x1b[0m";
+
+/// Returns `true` if `escape` is an SGR sequence that resets all attributes.
+pub(crate) fn is_reset(escape: &str) -> bool {
+    escape == RESET || escape == "\x1b[m"
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::Color;
+    use super::*;
+
+    #[test]
+    fn visible_width_and_strip() {
+        let text = format!("a{}c", Color::Red.bold().paint("bβ"));
+        assert_eq!(visible_width(&text), 4);
+        assert_eq!(strip_escapes(&text), "abβc");
+        assert_eq!(visible_width("日本\u{1b}x"), 5);
+    }
+
+    #[test]
+    fn truncate_preserves_escapes() {
+        let text = format!("{}", Color::Red.paint("abcdef"));
+        assert_eq!(truncate(&text, 6, "…"), text);
+        assert_eq!(truncate(&text, 4, "…"), "\x1b[31mabc…\x1b[0m");
+        assert_eq!(truncate("abcdef", 3, "…"), "ab…");
+        assert_eq!(truncate("日本語", 4, "…"), "日…");
+        // The ellipsis doesn't fit either.
+        assert_eq!(truncate("abcdef", 2, "[...]"), "[.");
+        assert_eq!(truncate("abcdef", 0, "…"), "");
+        assert_eq!(truncate("日本語", 1, "……"), "…");
+    }
+
+    #[test]
+    fn layer_reapplies_style() {
+        let text = format!("{}b", Color::Red.paint("a"));
+        assert_eq!(
+            layer(Style::new().dimmed(), &text),
+            "\x1b[2m\x1b[31ma\x1b[0m\x1b[2mb\x1b[0m",
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Assertions that render both values with their `ColorDisplay` impls when
+//! they fail, see `assert_color_eq!` and `assert_diff_eq!`.
+
+use crate::{
+    ansi, ColorDisplay,
+    diff::{ColorDiff, DiffFormat},
+    table::Alignment,
+};
+use std::fmt::{Arguments, Write};
+use std::io::IsTerminal;
+
+/// Asserts that two values are equal.  On failure, panics with both values
+/// rendered side by side using `format`, in the way of `assert_eq!`.
+/// The values are rendered in monochrome if stderr isn't a terminal.
+///
+/// ```
+/// use color_your_life::{assert_color_eq, primitive::U8Format, slice::SliceFormat, Format};
+///
+/// let format = SliceFormat::<U8Format>::colored(0);
+/// assert_color_eq!([1u8, 2][..], [1u8, 2][..], format);
+/// ```
+#[macro_export]
+macro_rules! assert_color_eq {
+    ($left:expr, $right:expr, $format:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => if !(*left == *right) {
+                $crate::assert::side_by_side_failed(left, right, &$format, None);
+            },
+        }
+    };
+    ($left:expr, $right:expr, $format:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => if !(*left == *right) {
+                $crate::assert::side_by_side_failed(
+                    left, right, &$format, Some(format_args!($($arg)+)),
+                );
+            },
+        }
+    };
+}
+
+/// Like `assert_color_eq!`, but on failure panics with a diff from the left
+/// to the right value, see `diff::ColorDiff`.
+#[macro_export]
+macro_rules! assert_diff_eq {
+    ($left:expr, $right:expr, $format:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => if !(*left == *right) {
+                $crate::assert::diff_failed(left, right, &$format, None);
+            },
+        }
+    };
+    ($left:expr, $right:expr, $format:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => if !(*left == *right) {
+                $crate::assert::diff_failed(
+                    left, right, &$format, Some(format_args!($($arg)+)),
+                );
+            },
+        }
+    };
+}
+
+/// Returns `true` if failure messages are written in color, i.e. if stderr
+/// is a terminal and the `NO_COLOR` environment variable isn't set.
+pub fn use_color() -> bool {
+    std::env::var_os("NO_COLOR").is_none() && std::io::stderr().is_terminal()
+}
+
+/// `left` and `right` rendered with `format`, in two columns.  Rows that
+/// differ are marked with a `!`.
+pub fn side_by_side<T, F>(
+    left: &T,
+    right: &T,
+    format: &F,
+    color: bool,
+) -> Result<String, std::fmt::Error>
+where
+    T: ColorDisplay<F> + ?Sized,
+{
+    let render = |value: &T| {
+        let mut rendered = String::new();
+        value.color_fmt(&mut rendered, format)?;
+        if !color {
+            rendered = ansi::strip_escapes(&rendered);
+        }
+        Ok::<_, std::fmt::Error>(rendered)
+    };
+    let (left, right) = (render(left)?, render(right)?);
+    let left: Vec<&str> = left.lines().collect();
+    let right: Vec<&str> = right.lines().collect();
+    let width = left.iter()
+        .map(|line| ansi::visible_width(line))
+        .chain(["left".len()])
+        .max()
+        .unwrap_or_default();
+    let mut view = format!("  {} │ right", Alignment::Left.pad("left", width));
+    for row in 0..left.len().max(right.len()) {
+        let (l, r) = (left.get(row), right.get(row));
+        let marker = if l == r { ' ' } else { '!' };
+        let l = Alignment::Left.pad(l.copied().unwrap_or_default(), width);
+        write!(view, "\n{marker} {l} │ {}", r.copied().unwrap_or_default())?;
+    }
+    Ok(view)
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn side_by_side_failed<T, F>(
+    left: &T,
+    right: &T,
+    format: &F,
+    message: Option<Arguments>,
+) -> !
+where
+    T: ColorDisplay<F> + ?Sized,
+{
+    let view = side_by_side(left, right, format, use_color())
+        .unwrap_or_else(|_| String::from("<failed to render the values>"));
+    failed(view, message)
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn diff_failed<T, F>(
+    left: &T,
+    right: &T,
+    format: &F,
+    message: Option<Arguments>,
+) -> !
+where
+    T: ColorDiff<F> + ?Sized,
+{
+    let diff_format = if use_color() {
+        DiffFormat::colored()
+    } else {
+        DiffFormat::monochrome()
+    };
+    let mut view = String::new();
+    if left.color_diff(right, &mut view, format, &diff_format).is_err() {
+        view = String::from("<failed to render the values>");
+    }
+    failed(view, message)
+}
+
+#[track_caller]
+fn failed(view: String, message: Option<Arguments>) -> ! {
+    match message {
+        Some(message) => {
+            panic!("assertion `left == right` failed: {message}\n{view}")
+        },
+        None => panic!("assertion `left == right` failed\n{view}"),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        btree_map::BTreeMapFormat,
+        primitive::U8Format,
+        slice::SliceFormat,
+        str::StrFormat,
+        Format,
+    };
+    use std::collections::BTreeMap;
+    use super::*;
+
+    #[test]
+    fn side_by_side_view() -> std::fmt::Result {
+        let format = SliceFormat::<U8Format>::colored(0);
+        let view = side_by_side(&[1u8, 2, 30][..], &[1, 3][..], &format, false)?;
+        let expected = concat!(
+            "  left │ right\n",
+            "  1    │ 1\n",
+            "! 2    │ 3\n",
+            "! 30   │ ",
+        );
+        assert_eq!(view, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn passing_assertions() {
+        let format = SliceFormat::<U8Format>::colored(0);
+        assert_color_eq!([1u8, 2][..], [1u8, 2][..], format);
+        assert_diff_eq!([1u8, 2][..], [1u8, 2][..], format, "with {}", "a message");
+    }
+
+    #[test]
+    #[should_panic(expected = "failed: nested\n  left │ right\n  a: 1 │ a: 1\n! b: 2 │ b: 3")]
+    fn failing_assertion() {
+        let left: BTreeMap<&str, u8> = [("a", 1), ("b", 2)].into_iter().collect();
+        let right: BTreeMap<&str, u8> = [("a", 1), ("b", 3)].into_iter().collect();
+        let format = BTreeMapFormat::<StrFormat, U8Format>::monochrome(0);
+        assert_color_eq!(left, right, format, "nested");
+    }
+
+    #[test]
+    #[should_panic(expected = "failed\n  1\n- 2\n+ 3")]
+    fn failing_diff_assertion() {
+        let format = SliceFormat::<U8Format>::monochrome(0);
+        assert_diff_eq!([1u8, 2][..], [1u8, 3][..], format);
+    }
+}
@@ -1,6 +1,7 @@
-//!
+//! `ColorDisplay` support for `bool`.
 
-use crate::{compute_leaf_style, Color, ColorDisplay, Format, StyleDesc};
+use crate::{Color, ColorDisplay, Context, Format, StyleDesc, Text};
+use crate::leaf::{impl_leaf_format, write_leaf};
 use std::fmt::Write;
 
 impl ColorDisplay<BoolFormat> for bool {
@@ -9,43 +10,79 @@ impl ColorDisplay<BoolFormat> for bool {
         sink: &mut impl Write,
         format: &BoolFormat,
     ) -> std::fmt::Result {
-        self.write_indentation(sink, format.indent, format)?;
-        let style = compute_leaf_style(format.style_desc);
-        write!(sink, "{}", style.paint(if *self { "true" } else { "false" }))
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &BoolFormat,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        let style = ctx.leaf_style(format.style_desc);
+        let text = style.paint(if *self { "true" } else { "false" });
+        write_leaf(self, sink, format, ctx, &text.to_string())
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct BoolFormat {
     pub indent: u16,
-    pub prefix: &'static str,
+    pub prefix: Text,
+    pub prefix_style_desc: Option<StyleDesc>,
+    pub delimiter: Text,
+    pub delimiter_style_desc: Option<StyleDesc>,
+    pub suffix: Text,
+    pub suffix_style_desc: Option<StyleDesc>,
     pub style_desc: Option<StyleDesc>,
 }
 
-impl Format for BoolFormat {
-    fn colored(indent: u16) -> Self {
+impl_leaf_format!(BoolFormat);
+
+impl BoolFormat {
+    pub const fn colored(indent: u16) -> Self {
         Self {
             indent,
-            prefix: "",
-            style_desc: Some(StyleDesc {
-                color: Color::Purple,
-                bold: true,
-                italic: false,
-                underline: false,
-                dimmed: false,
-            }),
+            prefix: Text::Borrowed(""),
+            prefix_style_desc: Some(StyleDesc::PUNCTUATION),
+            delimiter: Text::Borrowed(""),
+            delimiter_style_desc: Some(StyleDesc::PUNCTUATION),
+            suffix: Text::Borrowed(""),
+            suffix_style_desc: Some(StyleDesc::PUNCTUATION),
+            style_desc: Some(StyleDesc::fg(Color::Purple).bold()),
         }
     }
 
-    fn monochrome(indent: u16) -> Self {
+    pub const fn monochrome(indent: u16) -> Self {
         Self {
             indent,
-            prefix: "",
+            prefix: Text::Borrowed(""),
+            prefix_style_desc: None,
+            delimiter: Text::Borrowed(""),
+            delimiter_style_desc: None,
+            suffix: Text::Borrowed(""),
+            suffix_style_desc: None,
             style_desc: None,
         }
     }
 }
 
+impl Format for BoolFormat {
+    fn colored(indent: u16) -> Self {
+        Self::colored(indent)
+    }
+
+    fn monochrome(indent: u16) -> Self {
+        Self::monochrome(indent)
+    }
+}
+
+impl Default for BoolFormat {
+    fn default() -> Self {
+        Self::colored(0)
+    }
+}
+
 
 #[cfg(test)]
 mod test {
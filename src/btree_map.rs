@@ -1,6 +1,10 @@
-//!
+//! `ColorDisplay` support for `BTreeMap<K, V>`.
 
 use super::*;
+use crate::layout::{
+    elide, impl_collection_builders, write_items, write_key,
+    CollectionFormat, KeyAlignment, Layout, Truncation,
+};
 use std::collections::BTreeMap;
 
 impl<KF, VF, K, V> ColorDisplay<BTreeMapFormat<KF, VF>> for BTreeMap<K, V>
@@ -13,27 +17,51 @@ where
         sink: &mut impl Write,
         format: &BTreeMapFormat<KF, VF>,
     ) -> std::fmt::Result {
-        self.write_newlines(sink, format.prefix_newlines, &format)?;
-        for (idx, (key, value)) in self.iter().enumerate() {
-            if idx > 0 {
-                self.write_newlines(sink, format.intersperse_newlines, &format)?;
-            }
-            key.color_fmt(sink, &format.key_format)?;
-            write!(sink, "{}", format.key_value_separator)?;
-            value.color_fmt(sink, &format.value_format)?;
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
 
-        }
-        self.write_newlines(sink, format.suffix_newlines, &format)?;
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &BTreeMapFormat<KF, VF>,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        self.write_newlines_in(sink, format.prefix_newlines, format, ctx)?;
+        let key_width = format.key_alignment.map(|alignment| {
+            alignment.width(self.keys(), &format.key_format)
+        }).transpose()?;
+        write_items(
+            self, sink, format, ctx,
+            elide(self.iter(), format.truncation.limit),
+            |sink, ctx, (key, value)| {
+                let alignment = format.key_alignment.as_ref().zip(key_width);
+                write_key(sink, ctx, key, &format.key_format, alignment)?;
+                let separator = ctx.paint(
+                    &format.key_value_separator,
+                    format.key_value_separator_style_desc,
+                );
+                ctx.write(sink, &separator)?;
+                value.color_fmt_in(sink, &format.value_format, ctx)
+            },
+        )?;
+        self.write_newlines_in(sink, format.suffix_newlines, format, ctx)?;
         Ok(())
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct BTreeMapFormat<KF, VF> {
     pub prefix_newlines: u16,
     pub intersperse_newlines: u16,
     pub suffix_newlines: u16,
-    pub key_value_separator: &'static str,
+    pub key_value_separator: Text,
+    pub key_value_separator_style_desc: Option<StyleDesc>,
+    pub layout: Layout,
+    pub truncation: Truncation,
+    /// Layered over the styles of the descendants, see `StyleOverlay`.
+    pub style_overlay: StyleOverlay,
+    /// How to pad the keys, if at all.
+    pub key_alignment: Option<KeyAlignment>,
     pub key_format: KF,
     pub value_format: VF,
 }
@@ -44,7 +72,12 @@ impl<KF: Format, VF: Format> Format for BTreeMapFormat<KF, VF> {
             prefix_newlines: 0,
             intersperse_newlines: 1,
             suffix_newlines: 0,
-            key_value_separator: ": ",
+            key_value_separator: ": ".into(),
+            key_value_separator_style_desc: Some(StyleDesc::PUNCTUATION),
+            layout: Layout::Lines,
+            truncation: Truncation::colored(indent),
+            style_overlay: StyleOverlay::INHERIT,
+            key_alignment: None,
             key_format: KF::colored(indent),
             value_format: VF::colored(indent),
         }
@@ -55,13 +88,44 @@ impl<KF: Format, VF: Format> Format for BTreeMapFormat<KF, VF> {
             prefix_newlines: 0,
             intersperse_newlines: 1,
             suffix_newlines: 0,
-            key_value_separator: ": ",
+            key_value_separator: ": ".into(),
+            key_value_separator_style_desc: None,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(indent),
+            style_overlay: StyleOverlay::INHERIT,
+            key_alignment: None,
             key_format: KF::monochrome(indent),
             value_format: VF::monochrome(indent),
         }
     }
 }
 
+impl<KF: Format, VF: Format> Default for BTreeMapFormat<KF, VF> {
+    fn default() -> Self {
+        Self::colored(0)
+    }
+}
+
+impl_collection_builders!(impl<KF, VF> BTreeMapFormat<KF, VF>);
+
+impl<KF, VF> CollectionFormat for BTreeMapFormat<KF, VF> {
+    fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    fn intersperse_newlines(&self) -> u16 {
+        self.intersperse_newlines
+    }
+
+    fn truncation(&self) -> &Truncation {
+        &self.truncation
+    }
+
+    fn style_overlay(&self) -> &StyleOverlay {
+        &self.style_overlay
+    }
+}
+
 
 #[cfg(test)]
 mod test {
@@ -83,15 +147,21 @@ mod test {
             prefix_newlines: 1,
             intersperse_newlines: 1,
             suffix_newlines: 1,
-            key_value_separator: " = ",
+            key_value_separator: " = ".into(),
+            key_value_separator_style_desc: None,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(0),
+            style_overlay: StyleOverlay::INHERIT,
+            key_alignment: None,
             key_format: U8Format {
-                prefix: "-> ",
+                prefix: "-> ".into(),
                 ..U8Format::colored(1)
             },
             value_format: StrFormat::colored(0),
         })?;
+        let arrow = Color::White.dimmed().paint("-> ");
         let expected = format!(
-            "\n    -> {} = {}\n    -> {} = {}\n    -> {} = {}\n",
+            "\n    {arrow}{} = {}\n    {arrow}{} = {}\n    {arrow}{} = {}\n",
             Color::Blue.bold().paint("10"),
             Color::Green.paint("ten"),
             Color::Blue.bold().paint("20"),
@@ -102,4 +172,57 @@ mod test {
         assert_eq!(sink, expected);
         Ok(())
     }
+
+    #[test]
+    fn aligned_color_fmt() -> std::fmt::Result {
+        let mut map: BTreeMap<&str, u8> = BTreeMap::new();
+        map.insert("a", 1);
+        map.insert("bbb", 2);
+        map.insert("a very long key", 3);
+        for (key_alignment, expected) in [
+            (KeyAlignment::left(8), "    a  : 1\n    a very long key: 3\n    bbb: 2"),
+            (KeyAlignment::right(8), "      a: 1\n    a very long key: 3\n    bbb: 2"),
+        ] {
+            let mut sink = String::with_capacity(1024);
+            map.color_fmt(&mut sink, &BTreeMapFormat {
+                key_alignment: Some(key_alignment),
+                key_format: StrFormat::monochrome(1),
+                ..BTreeMapFormat::<_, U8Format>::monochrome(0)
+            })?;
+            assert_eq!(sink, expected);
+        }
+
+        // Padding is applied outside of the style of the key.
+        let mut sink = String::with_capacity(1024);
+        map.color_fmt(&mut sink, &BTreeMapFormat {
+            key_alignment: Some(KeyAlignment::right(3)),
+            ..BTreeMapFormat::<StrFormat, U8Format>::colored(0)
+        })?;
+        let colon = Color::White.dimmed().paint(": ");
+        let expected = format!(
+            "  {}{colon}{}\n{}{colon}{}\n{}{colon}{}",
+            Color::Green.paint("a"),
+            Color::Blue.bold().paint("1"),
+            Color::Green.paint("a very long key"),
+            Color::Blue.bold().paint("3"),
+            Color::Green.paint("bbb"),
+            Color::Blue.bold().paint("2"),
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn owned_text_color_fmt() -> std::fmt::Result {
+        let map: BTreeMap<&str, u8> = [("a", 1), ("b", 2)].into_iter().collect();
+        // E.g. read from a config file.
+        let separator = String::from(" := ");
+        let mut sink = String::with_capacity(1024);
+        map.color_fmt(&mut sink, &BTreeMapFormat {
+            key_value_separator: separator.into(),
+            ..BTreeMapFormat::<StrFormat, U8Format>::monochrome(0).inline()
+        })?;
+        assert_eq!(sink, "a := 1, b := 2");
+        Ok(())
+    }
 }
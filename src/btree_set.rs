@@ -1,6 +1,10 @@
-//!
+//! `ColorDisplay` support for `BTreeSet<T>`.
 
 use super::*;
+use crate::layout::{
+    elide, impl_collection_builders, write_items, CollectionFormat,
+    Layout, Truncation,
+};
 use std::collections::BTreeSet;
 
 impl<TF, T> ColorDisplay<BTreeSetFormat<TF>> for BTreeSet<T>
@@ -12,23 +16,35 @@ where
         sink: &mut impl Write,
         format: &BTreeSetFormat<TF>,
     ) -> std::fmt::Result {
-        self.write_newlines(sink, format.prefix_newlines, &format)?;
-        for (idx, item) in self.iter().enumerate() {
-            if idx > 0 {
-                self.write_newlines(sink, format.intersperse_newlines, &format)?;
-            }
-            item.color_fmt(sink, &format.item_format)?;
-        }
-        self.write_newlines(sink, format.suffix_newlines, &format)?;
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &BTreeSetFormat<TF>,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        self.write_newlines_in(sink, format.prefix_newlines, format, ctx)?;
+        write_items(
+            self, sink, format, ctx,
+            elide(self.iter(), format.truncation.limit),
+            |sink, ctx, item| item.color_fmt_in(sink, &format.item_format, ctx),
+        )?;
+        self.write_newlines_in(sink, format.suffix_newlines, format, ctx)?;
         Ok(())
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct BTreeSetFormat<TF> {
     pub prefix_newlines: u16,
     pub intersperse_newlines: u16,
     pub suffix_newlines: u16,
+    pub layout: Layout,
+    pub truncation: Truncation,
+    /// Layered over the styles of the descendants, see `StyleOverlay`.
+    pub style_overlay: StyleOverlay,
     pub item_format: TF,
 }
 
@@ -38,6 +54,9 @@ impl<TF: Format> Format for BTreeSetFormat<TF> {
             prefix_newlines: 0,
             intersperse_newlines: 1,
             suffix_newlines: 0,
+            layout: Layout::Lines,
+            truncation: Truncation::colored(indent),
+            style_overlay: StyleOverlay::INHERIT,
             item_format: TF::colored(indent),
         }
     }
@@ -47,11 +66,40 @@ impl<TF: Format> Format for BTreeSetFormat<TF> {
             prefix_newlines: 0,
             intersperse_newlines: 1,
             suffix_newlines: 0,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(indent),
+            style_overlay: StyleOverlay::INHERIT,
             item_format: TF::monochrome(indent),
         }
     }
 }
 
+impl<TF: Format> Default for BTreeSetFormat<TF> {
+    fn default() -> Self {
+        Self::colored(0)
+    }
+}
+
+impl_collection_builders!(impl<TF> BTreeSetFormat<TF>);
+
+impl<TF> CollectionFormat for BTreeSetFormat<TF> {
+    fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    fn intersperse_newlines(&self) -> u16 {
+        self.intersperse_newlines
+    }
+
+    fn truncation(&self) -> &Truncation {
+        &self.truncation
+    }
+
+    fn style_overlay(&self) -> &StyleOverlay {
+        &self.style_overlay
+    }
+}
+
 
 #[cfg(test)]
 mod test {
@@ -72,13 +120,17 @@ mod test {
             prefix_newlines: 1,
             intersperse_newlines: 1,
             suffix_newlines: 1,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(0),
+            style_overlay: StyleOverlay::INHERIT,
             item_format: U8Format {
-                prefix: "->",
+                prefix: "->".into(),
                 ..U8Format::colored(1)
             },
         })?;
+        let arrow = Color::White.dimmed().paint("->");
         let expected = format!(
-            "\n    ->{}\n    ->{}\n    ->{}\n",
+            "\n    {arrow}{}\n    {arrow}{}\n    {arrow}{}\n",
             Color::Blue.bold().paint("10"),
             Color::Blue.bold().paint("20"),
             Color::Blue.bold().paint("30"),
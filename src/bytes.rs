@@ -0,0 +1,528 @@
+//! `ColorDisplay` support for byte slices `[u8]`, `&[u8]` and `Vec<u8>`,
+//! rendered as a hexdump, as inline hex or as base64 rather than byte by byte.
+
+use crate::{Color, ColorDisplay, Context, Format, StyleDesc};
+use crate::layout::{elide, Entry, Limit, Truncation};
+use std::fmt::Write;
+
+impl ColorDisplay<BytesFormat> for [u8] {
+    fn color_fmt(
+        &self,
+        sink: &mut impl Write,
+        format: &BytesFormat,
+    ) -> std::fmt::Result {
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &BytesFormat,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        match format.mode {
+            BytesMode::Dump => {
+                let per_row = format.bytes_per_row.max(1);
+                let rows = self.chunks(per_row).enumerate();
+                for (idx, entry) in elide(rows, format.truncation.limit).enumerate() {
+                    if idx > 0 {
+                        self.write_newlines_in(sink, 1, format, ctx)?;
+                    }
+                    let (indent, row) = match entry {
+                        Entry::Item((row, chunk)) => {
+                            let row = dump_row(chunk, row * per_row, format, ctx);
+                            (format.indent, row)
+                        },
+                        Entry::Elided(count) => {
+                            let row = format.elision(count, ctx);
+                            (format.truncation.indent, row)
+                        },
+                    };
+                    let indent = ctx.indentation(indent);
+                    self.write_indentation_in(sink, indent, format, ctx)?;
+                    ctx.write(sink, &row)?;
+                }
+                Ok(())
+            },
+            BytesMode::Hex => {
+                let indent = ctx.indentation(format.indent);
+                self.write_indentation_in(sink, indent, format, ctx)?;
+                let mut hex = Runs::with_capacity(2 * self.len());
+                // The elision marker is separated from the bytes by spaces.
+                let mut elided = false;
+                for (idx, entry) in elide(self.iter(), format.truncation.limit).enumerate() {
+                    match entry {
+                        Entry::Item(&byte) => {
+                            if std::mem::take(&mut elided) {
+                                hex.push_unstyled(" ");
+                            }
+                            let style = ctx.leaf_style(format.class_style(byte));
+                            hex.push(&format!("{byte:02x}"), style);
+                        },
+                        Entry::Elided(count) => {
+                            if idx > 0 {
+                                hex.push_unstyled(" ");
+                            }
+                            hex.push_unstyled(&format.elision(count, ctx));
+                            elided = true;
+                        },
+                    }
+                }
+                ctx.write(sink, &hex.finish())
+            },
+            BytesMode::Base64 => {
+                let indent = ctx.indentation(format.indent);
+                self.write_indentation_in(sink, indent, format, ctx)?;
+                let style = ctx.leaf_style(format.style_desc);
+                ctx.write(sink, &style.paint(base64(self)).to_string())
+            },
+        }
+    }
+}
+
+impl ColorDisplay<BytesFormat> for Vec<u8> {
+    fn color_fmt(
+        &self,
+        sink: &mut impl Write,
+        format: &BytesFormat,
+    ) -> std::fmt::Result {
+        self.as_slice().color_fmt(sink, format)
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &BytesFormat,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        self.as_slice().color_fmt_in(sink, format, ctx)
+    }
+}
+
+impl ColorDisplay<BytesFormat> for &'_ [u8] {
+    fn color_fmt(
+        &self,
+        sink: &mut impl Write,
+        format: &BytesFormat,
+    ) -> std::fmt::Result {
+        (**self).color_fmt(sink, format)
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &BytesFormat,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        (**self).color_fmt_in(sink, format, ctx)
+    }
+}
+
+/// Render a row of the classic `hexdump -C` layout: an offset column,
+/// `format.bytes_per_row` hex bytes split into groups of `format.group_size`,
+/// and an ASCII gutter.  A short last row is padded so that its gutter lines
+/// up with the rows above it.  The styles are resolved in `ctx`.
+fn dump_row(
+    chunk: &[u8],
+    offset: usize,
+    format: &BytesFormat,
+    ctx: &Context,
+) -> String {
+    let per_row = format.bytes_per_row.max(1);
+    let mut row = Runs::with_capacity(8 * per_row);
+    if format.offsets {
+        row.push(&format!("{offset:08x}"), ctx.leaf_style(format.offset_style));
+        row.push_unstyled("  ");
+    }
+    for idx in 0..per_row {
+        let mut separator = String::new();
+        if idx > 0 {
+            separator.push(' ');
+            if format.group_size > 0 && idx % format.group_size == 0 {
+                separator.push(' ');
+            }
+        }
+        match chunk.get(idx) {
+            Some(&byte) => {
+                row.push_separator(&separator);
+                let style = ctx.leaf_style(format.class_style(byte));
+                row.push(&format!("{byte:02x}"), style);
+            },
+            None => row.push_unstyled(&(separator + "  ")),
+        }
+    }
+    if format.ascii {
+        row.push_unstyled("  |");
+        for &byte in chunk {
+            let style = ctx.leaf_style(format.class_style(byte));
+            let c = match ByteClass::of(byte) {
+                ByteClass::Printable => byte as char,
+                ByteClass::Whitespace if byte == b' ' => ' ',
+                _ => '.',
+            };
+            row.push(c.encode_utf8(&mut [0; 4]), style);
+        }
+        row.push_unstyled("|");
+    }
+    row.finish()
+}
+
+/// Text in which consecutive pieces with the same style are painted as a
+/// single run, rather than each with escape sequences of its own.
+struct Runs {
+    painted: String,
+    run: String,
+    style: ansi_term::Style,
+}
+
+impl Runs {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            painted: String::with_capacity(capacity),
+            run: String::new(),
+            style: ansi_term::Style::default(),
+        }
+    }
+
+    fn push(&mut self, text: &str, style: ansi_term::Style) {
+        if style != self.style {
+            self.flush();
+            self.style = style;
+        }
+        self.run.push_str(text);
+    }
+
+    /// Push `separator` as part of the current run, whatever its style.
+    fn push_separator(&mut self, separator: &str) {
+        self.run.push_str(separator);
+    }
+
+    fn push_unstyled(&mut self, text: &str) {
+        self.flush();
+        self.painted.push_str(text);
+    }
+
+    fn flush(&mut self) {
+        if !self.run.is_empty() {
+            let _ = write!(self.painted, "{}", self.style.paint(self.run.as_str()));
+            self.run.clear();
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.flush();
+        self.painted
+    }
+}
+
+/// Encode `bytes` using the standard base64 alphabet, with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let sextet = (n >> (18 - 6 * i)) & 0x3f;
+                encoded.push(ALPHABET[sextet as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// The class of a byte, used to pick the style it is painted with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteClass {
+    /// The byte `0x00`.
+    Zero,
+    /// ASCII graphic characters, i.e. `0x21..=0x7e`.
+    Printable,
+    /// ASCII whitespace, i.e. space, `\t`, `\n`, `\x0b`, `\x0c` and `\r`.
+    Whitespace,
+    /// The remaining ASCII control characters.
+    Control,
+    /// Any byte with the high bit set, i.e. `0x80..=0xff`.
+    HighBit,
+}
+
+impl ByteClass {
+    pub fn of(byte: u8) -> Self {
+        match byte {
+            0x00 => Self::Zero,
+            b if b.is_ascii_graphic() => Self::Printable,
+            b if b.is_ascii_whitespace() || b == 0x0b => Self::Whitespace,
+            b if b.is_ascii_control() => Self::Control,
+            _ => Self::HighBit,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BytesMode {
+    /// An offset column, rows of hex bytes and an ASCII gutter.
+    Dump,
+    /// All bytes as a single run of lowercase hex digits.
+    Hex,
+    /// All bytes as a single base64 string.
+    Base64,
+}
+
+#[derive(Clone, Copy)]
+pub struct BytesFormat {
+    pub indent: u16,
+    pub mode: BytesMode,
+    /// The number of bytes per row in `BytesMode::Dump`.
+    pub bytes_per_row: usize,
+    /// The number of bytes per group within a row in `BytesMode::Dump`.
+    /// Groups are separated by an extra space. A value of 0 disables grouping.
+    pub group_size: usize,
+    /// Whether to write the offset column in `BytesMode::Dump`.
+    pub offsets: bool,
+    /// Whether to write the ASCII gutter in `BytesMode::Dump`.
+    pub ascii: bool,
+    /// The style of the offset column.
+    pub offset_style: Option<StyleDesc>,
+    /// The styles of the individual bytes, per `ByteClass`.
+    pub class_styles: ByteClassStyles,
+    /// The style of the text written in `BytesMode::Base64`.
+    pub style_desc: Option<StyleDesc>,
+    /// The rows to write in `BytesMode::Dump`, or the bytes to write in
+    /// `BytesMode::Hex`.  Base64 is always written in full, as a part of it
+    /// couldn't be decoded.
+    pub truncation: Truncation,
+}
+
+impl BytesFormat {
+    /// Write only the first `max_items` rows or bytes, see `truncation`.
+    pub fn max_items(self, max_items: usize) -> Self {
+        self.limit(Limit::Head(max_items))
+    }
+
+    /// Write only the first `head` and the last `tail` rows or bytes, see
+    /// `truncation`.
+    pub fn head_tail(self, head: usize, tail: usize) -> Self {
+        self.limit(Limit::HeadTail { head, tail })
+    }
+
+    fn limit(self, limit: Limit) -> Self {
+        let truncation = Truncation { limit: Some(limit), ..self.truncation };
+        Self { truncation, ..self }
+    }
+
+    /// The marker that replaces `count` omitted rows or bytes.
+    fn elision(&self, count: usize, ctx: &Context) -> String {
+        let style = ctx.leaf_style(self.truncation.style_desc);
+        style.paint(format!("… {count} more")).to_string()
+    }
+
+    pub fn class_style(&self, byte: u8) -> Option<StyleDesc> {
+        match ByteClass::of(byte) {
+            ByteClass::Zero => self.class_styles.zero,
+            ByteClass::Printable => self.class_styles.printable,
+            ByteClass::Whitespace => self.class_styles.whitespace,
+            ByteClass::Control => self.class_styles.control,
+            ByteClass::HighBit => self.class_styles.high_bit,
+        }
+    }
+}
+
+impl Format for BytesFormat {
+    fn colored(indent: u16) -> Self {
+        Self {
+            indent,
+            mode: BytesMode::Dump,
+            bytes_per_row: 16,
+            group_size: 8,
+            offsets: true,
+            ascii: true,
+            offset_style: Some(StyleDesc::fg(Color::White).dimmed()),
+            class_styles: ByteClassStyles {
+                zero: Some(StyleDesc::fg(Color::White).dimmed()),
+                printable: Some(StyleDesc::fg(Color::Cyan)),
+                whitespace: Some(StyleDesc::fg(Color::Green)),
+                control: Some(StyleDesc::fg(Color::Purple)),
+                high_bit: Some(StyleDesc::fg(Color::Yellow)),
+            },
+            style_desc: Some(StyleDesc::fg(Color::Cyan)),
+            truncation: Truncation::colored(indent),
+        }
+    }
+
+    fn monochrome(indent: u16) -> Self {
+        Self {
+            indent,
+            mode: BytesMode::Dump,
+            bytes_per_row: 16,
+            group_size: 8,
+            offsets: true,
+            ascii: true,
+            offset_style: None,
+            class_styles: ByteClassStyles {
+                zero: None,
+                printable: None,
+                whitespace: None,
+                control: None,
+                high_bit: None,
+            },
+            style_desc: None,
+            truncation: Truncation::monochrome(indent),
+        }
+    }
+}
+
+impl Default for BytesFormat {
+    fn default() -> Self {
+        Self::colored(0)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ByteClassStyles {
+    pub zero: Option<StyleDesc>,
+    pub printable: Option<StyleDesc>,
+    pub whitespace: Option<StyleDesc>,
+    pub control: Option<StyleDesc>,
+    pub high_bit: Option<StyleDesc>,
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{Color, ColorDisplay, option::OptionFormat, vec::VecFormat};
+    use super::*;
+
+    #[test]
+    fn dump_color_fmt() -> std::fmt::Result {
+        let bytes: &[u8] = b"Hello,\n\0\x01\xff world!";
+        let mut sink = String::with_capacity(1024);
+        bytes.color_fmt(&mut sink, &BytesFormat::monochrome(1))?;
+        let expected = concat!(
+            "    00000000  48 65 6c 6c 6f 2c 0a 00  01 ff 20 77 6f 72 6c 64  |Hello,.... world|\n",
+            "    00000010  21                                                |!|",
+        );
+        assert_eq!(sink, expected);
+
+        let mut sink = String::with_capacity(1024);
+        bytes[..2].color_fmt(&mut sink, &BytesFormat::colored(0))?;
+        let cyan = Color::Cyan.normal();
+        let expected = format!(
+            "{}  {}{}  |{}|",
+            Color::White.dimmed().paint("00000000"),
+            cyan.paint("48 65"),
+            " ".repeat(3 * 14 + 1),
+            cyan.paint("He"),
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn hex_color_fmt() -> std::fmt::Result {
+        let bytes: Vec<u8> = vec![0x00, b'a', b'\t', 0x7f, 0x80];
+        let mut sink = String::with_capacity(1024);
+        bytes.color_fmt(&mut sink, &BytesFormat {
+            mode: BytesMode::Hex,
+            ..BytesFormat::colored(0)
+        })?;
+        let expected = format!(
+            "{}{}{}{}{}",
+            Color::White.dimmed().paint("00"),
+            Color::Cyan.normal().paint("61"),
+            Color::Green.normal().paint("09"),
+            Color::Purple.normal().paint("7f"),
+            Color::Yellow.normal().paint("80"),
+        );
+        assert_eq!(sink, expected);
+
+        // Runs of bytes of the same class are painted once.
+        let mut sink = String::with_capacity(1024);
+        b"ab\0\0".color_fmt(&mut sink, &BytesFormat {
+            mode: BytesMode::Hex,
+            ..BytesFormat::colored(0)
+        })?;
+        let expected = format!(
+            "{}{}",
+            Color::Cyan.normal().paint("6162"),
+            Color::White.dimmed().paint("0000"),
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn base64_color_fmt() -> std::fmt::Result {
+        for (bytes, expected) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foobar", "Zm9vYmFy"),
+        ] {
+            let mut sink = String::with_capacity(1024);
+            bytes.color_fmt(&mut sink, &BytesFormat {
+                mode: BytesMode::Base64,
+                ..BytesFormat::monochrome(0)
+            })?;
+            assert_eq!(sink, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn borrowed_color_fmt() -> std::fmt::Result {
+        let hex = BytesFormat { mode: BytesMode::Hex, ..BytesFormat::monochrome(0) };
+        let chunks: Vec<&[u8]> = vec![b"ab", b"c"];
+        let mut sink = String::with_capacity(1024);
+        chunks.color_fmt(&mut sink, &VecFormat::<BytesFormat> {
+            item_format: hex,
+            ..VecFormat::monochrome(0)
+        }.inline())?;
+        assert_eq!(sink, "6162, 63");
+
+        let mut sink = String::with_capacity(1024);
+        Some(&b"ab"[..]).color_fmt(&mut sink, &OptionFormat {
+            some_format: hex,
+            ..OptionFormat::monochrome(0)
+        })?;
+        assert_eq!(sink, "6162");
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_color_fmt() -> std::fmt::Result {
+        let bytes: Vec<u8> = (0..64).collect();
+        let mut sink = String::with_capacity(1024);
+        bytes.color_fmt(&mut sink, &BytesFormat {
+            ascii: false,
+            group_size: 0,
+            bytes_per_row: 8,
+            ..BytesFormat::monochrome(0)
+        }.head_tail(1, 1))?;
+        let expected = concat!(
+            "00000000  00 01 02 03 04 05 06 07\n",
+            "… 6 more\n",
+            "00000038  38 39 3a 3b 3c 3d 3e 3f",
+        );
+        assert_eq!(sink, expected);
+
+        let mut sink = String::with_capacity(1024);
+        bytes.color_fmt(&mut sink, &BytesFormat {
+            mode: BytesMode::Hex,
+            ..BytesFormat::monochrome(0)
+        }.head_tail(2, 1))?;
+        assert_eq!(sink, "0001 … 61 more 3f");
+
+        let mut sink = String::with_capacity(1024);
+        bytes.color_fmt(&mut sink, &BytesFormat {
+            mode: BytesMode::Hex,
+            ..BytesFormat::monochrome(0)
+        }.max_items(2))?;
+        assert_eq!(sink, "0001 … 62 more");
+        Ok(())
+    }
+}
@@ -1,8 +1,9 @@
-//!
+//! `ColorDisplay` support for `char`.
 
 use ansi_term::Color;
-use crate::{compute_leaf_style, ColorDisplay, Format, StyleDesc};
-use std::borrow::Cow;
+use crate::{ColorDisplay, Context, Format, StyleDesc, Text};
+use crate::highlight::{self, Highlights};
+use crate::leaf::{impl_leaf_format, write_leaf};
 use std::fmt::Write;
 
 impl ColorDisplay<CharFormat> for char {
@@ -11,53 +12,93 @@ impl ColorDisplay<CharFormat> for char {
         sink: &mut impl Write,
         format: &CharFormat,
     ) -> std::fmt::Result {
-        self.write_indentation(sink, format.indent, format)?;
-        write!(sink, "{}", format.prefix)?;
-        let style = compute_leaf_style(format.style_desc);
-        let d = format.delimiter;
-        write!(sink, "{d}{}{d}", style.paint(Cow::Owned(self.to_string())))
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &CharFormat,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        let text = self.to_string();
+        let owner = highlight::owners(&text, &format.highlights)[0];
+        let highlight = owner.map(|rule| &format.highlights[rule]);
+        let style = ctx.leaf_style(highlight::layered(format.style_desc, highlight));
+        let text = style.paint(text).to_string();
+        write_leaf(self, sink, format, ctx, &text)
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct CharFormat {
     pub indent: u16,
-    pub delimiter: &'static str,
-    pub prefix: &'static str,
+    pub delimiter: Text,
+    pub delimiter_style_desc: Option<StyleDesc>,
+    pub prefix: Text,
+    pub prefix_style_desc: Option<StyleDesc>,
+    pub suffix: Text,
+    pub suffix_style_desc: Option<StyleDesc>,
     pub style_desc: Option<StyleDesc>,
+    /// Rules to highlight the character with.  When several rules match,
+    /// the rule that comes first wins.
+    pub highlights: Highlights,
 }
 
-impl Format for CharFormat {
-    fn colored(indent: u16) -> Self {
+impl_leaf_format!(CharFormat);
+
+impl CharFormat {
+    pub const fn colored(indent: u16) -> Self {
         Self {
             indent,
-            delimiter: "",
-            prefix: "",
-            style_desc: Some(StyleDesc {
-                color: Color::Green,
-                bold: true,
-                italic: false,
-                underline: false,
-                dimmed: false,
-            }),
+            delimiter: Text::Borrowed(""),
+            delimiter_style_desc: Some(StyleDesc::PUNCTUATION),
+            prefix: Text::Borrowed(""),
+            prefix_style_desc: Some(StyleDesc::PUNCTUATION),
+            suffix: Text::Borrowed(""),
+            suffix_style_desc: Some(StyleDesc::PUNCTUATION),
+            style_desc: Some(StyleDesc::fg(Color::Green).bold()),
+            highlights: Highlights::Borrowed(&[]),
         }
     }
 
-    fn monochrome(indent: u16) -> Self {
+    pub const fn monochrome(indent: u16) -> Self {
         Self {
             indent,
-            delimiter: "",
-            prefix: "",
+            delimiter: Text::Borrowed(""),
+            delimiter_style_desc: None,
+            prefix: Text::Borrowed(""),
+            prefix_style_desc: None,
+            suffix: Text::Borrowed(""),
+            suffix_style_desc: None,
             style_desc: None,
+            highlights: Highlights::Borrowed(&[]),
         }
     }
 }
 
+impl Format for CharFormat {
+    fn colored(indent: u16) -> Self {
+        Self::colored(indent)
+    }
+
+    fn monochrome(indent: u16) -> Self {
+        Self::monochrome(indent)
+    }
+}
+
+impl Default for CharFormat {
+    fn default() -> Self {
+        Self::colored(0)
+    }
+}
+
 
 
 #[cfg(test)]
 mod test {
     use crate::{Color, ColorDisplay};
+    use crate::highlight::Highlight;
     use super::*;
 
     #[test]
@@ -65,7 +106,30 @@ mod test {
         let c = 'C';
         let mut sink = String::with_capacity(1024);
         c.color_fmt(&mut sink, &CharFormat::colored(0))?;
-        let expected = format!("{}", Color::Green.bold().paint(&c.to_string()));
+        let expected = format!("{}", Color::Green.bold().paint(c.to_string()));
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn highlighted_color_fmt() -> std::fmt::Result {
+        let mut sink = String::with_capacity(1024);
+        'x'.color_fmt(&mut sink, &CharFormat {
+            highlights: vec![Highlight::case_insensitive("X", StyleDesc {
+                color: Some(Color::Red),
+                background: None,
+                bold: false,
+                italic: false,
+                underline: true,
+                dimmed: false,
+                blink: false,
+                reverse: false,
+                hidden: false,
+                strikethrough: false,
+            })].into(),
+            ..CharFormat::colored(0)
+        })?;
+        let expected = format!("{}", Color::Red.bold().underline().paint("x"));
         assert_eq!(sink, expected);
         Ok(())
     }
@@ -0,0 +1,922 @@
+//! Structural diffs between two values, rendered as a unified view of the
+//! lines that were removed, added or left unchanged.
+//!
+//! Sequences are aligned on their longest common subsequence, and the
+//! entries of maps and sets are matched up by key.  Values that changed are
+//! diffed recursively, and unchanged values are written with their regular
+//! format.
+
+use crate::{
+    ansi, compute_leaf_style, Color, ColorDisplay, Context, StyleDesc, Text,
+    bool::BoolFormat,
+    btree_map::BTreeMapFormat,
+    btree_set::BTreeSetFormat,
+    char::CharFormat,
+    hash_map::HashMapFormat,
+    hash_set::HashSetFormat,
+    layout::{sort, Order},
+    option::OptionFormat,
+    result::ResultFormat,
+    slice::SliceFormat,
+    str::StrFormat,
+    vec::VecFormat,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use std::hash::Hash;
+use std::ops::Range;
+
+/// Types that can render a diff between two of their values.
+pub trait ColorDiff<F>: ColorDisplay<F> {
+    /// The lines of the diff from `self` to `other`, where both are rendered
+    /// with `format` as part of a larger rendering described by `ctx`.
+    fn diff_lines(
+        &self,
+        other: &Self,
+        format: &F,
+        ctx: &Context,
+    ) -> Result<Vec<DiffLine>, std::fmt::Error>;
+
+    /// Write the diff from `self` to `other` to `sink`.
+    fn color_diff(
+        &self,
+        other: &Self,
+        sink: &mut impl Write,
+        format: &F,
+        diff_format: &DiffFormat,
+    ) -> std::fmt::Result {
+        let lines = self.diff_lines(other, format, &Context::default())?;
+        diff_format.write_lines(sink, &lines)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Unchanged,
+    Removed,
+    Added,
+}
+
+/// A line of a rendered value, and how it changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffLine {
+    pub change: Change,
+    pub text: String,
+}
+
+/// Describes how the lines of a diff are written.
+#[derive(Clone)]
+pub struct DiffFormat {
+    pub unchanged_marker: Text,
+    pub removed_marker: Text,
+    pub added_marker: Text,
+    /// The number of unchanged lines that are kept before and after changed
+    /// ones.  Longer runs of unchanged lines are collapsed into a single
+    /// line.  When `None`, all unchanged lines are written.
+    pub context: Option<usize>,
+    /// The style of removed lines.  Their regular styles are replaced.
+    pub removed_style_desc: Option<StyleDesc>,
+    /// The style of added lines.  Their regular styles are replaced.
+    pub added_style_desc: Option<StyleDesc>,
+    /// The style of the lines that replace collapsed unchanged lines.
+    pub collapsed_style_desc: Option<StyleDesc>,
+}
+
+impl DiffFormat {
+    pub const fn colored() -> Self {
+        Self {
+            unchanged_marker: Text::Borrowed("  "),
+            removed_marker: Text::Borrowed("- "),
+            added_marker: Text::Borrowed("+ "),
+            context: Some(3),
+            removed_style_desc: Some(StyleDesc::fg(Color::Red)),
+            added_style_desc: Some(StyleDesc::fg(Color::Green)),
+            collapsed_style_desc: Some(StyleDesc::fg(Color::White).italic().dimmed()),
+        }
+    }
+
+    pub const fn monochrome() -> Self {
+        Self {
+            unchanged_marker: Text::Borrowed("  "),
+            removed_marker: Text::Borrowed("- "),
+            added_marker: Text::Borrowed("+ "),
+            context: Some(3),
+            removed_style_desc: None,
+            added_style_desc: None,
+            collapsed_style_desc: None,
+        }
+    }
+
+    /// Write `lines` to `sink`, each preceded by the marker of its change,
+    /// and with runs of unchanged lines collapsed according to `self.context`.
+    pub fn write_lines(
+        &self,
+        sink: &mut impl Write,
+        lines: &[DiffLine],
+    ) -> std::fmt::Result {
+        let removed_style = compute_leaf_style(self.removed_style_desc);
+        let added_style = compute_leaf_style(self.added_style_desc);
+        let collapsed_style = compute_leaf_style(self.collapsed_style_desc);
+        let mut out = Vec::with_capacity(lines.len());
+        let mut idx = 0;
+        while idx < lines.len() {
+            let line = &lines[idx];
+            let text = ansi::strip_escapes(&line.text);
+            match line.change {
+                Change::Removed => {
+                    let line = format!("{}{text}", self.removed_marker);
+                    out.push(removed_style.paint(line).to_string());
+                },
+                Change::Added => {
+                    let line = format!("{}{text}", self.added_marker);
+                    out.push(added_style.paint(line).to_string());
+                },
+                Change::Unchanged => {
+                    let end = lines[idx..].iter()
+                        .position(|line| line.change != Change::Unchanged)
+                        .map_or(lines.len(), |len| idx + len);
+                    let (head, tail) = match self.context {
+                        None => (end - idx, 0),
+                        Some(context) => (
+                            if idx == 0 { 0 } else { context },
+                            if end == lines.len() { 0 } else { context },
+                        ),
+                    };
+                    let collapsed = (end - idx).saturating_sub(head + tail);
+                    for (offset, line) in lines[idx..end].iter().enumerate() {
+                        if collapsed > 0 && offset == head {
+                            let line = format!(
+                                "{}… {collapsed} unchanged",
+                                self.unchanged_marker,
+                            );
+                            out.push(collapsed_style.paint(line).to_string());
+                        }
+                        if collapsed == 0 || offset < head || offset >= head + collapsed {
+                            let line = format!("{}{}", self.unchanged_marker, line.text);
+                            out.push(line);
+                        }
+                    }
+                    idx = end;
+                    continue;
+                },
+            }
+            idx += 1;
+        }
+        sink.write_str(&out.join("\n"))
+    }
+}
+
+/// The lines of `value` rendered with `format` as part of `ctx`,
+/// all of them marked with `change`.
+pub fn render_lines<T, F>(
+    value: &T,
+    format: &F,
+    ctx: &Context,
+    change: Change,
+) -> Result<Vec<DiffLine>, std::fmt::Error>
+where
+    T: ColorDisplay<F> + ?Sized,
+{
+    let mut ctx = Context { column: 0, ..ctx.clone() };
+    let mut rendered = String::new();
+    value.color_fmt_in(&mut rendered, format, &mut ctx)?;
+    Ok(split_lines(&rendered, change))
+}
+
+/// Split `rendered` into lines, ignoring a trailing newline.
+fn split_lines(rendered: &str, change: Change) -> Vec<DiffLine> {
+    let rendered = match rendered.strip_suffix('\n') {
+        Some(stripped) if !stripped.is_empty() => stripped,
+        _ => rendered,
+    };
+    rendered.split('\n')
+        .map(|line| DiffLine {
+            change,
+            text: line.trim_end_matches('\r').to_string(),
+        })
+        .collect()
+}
+
+/// The diff of two values that are either equal as a whole, or not at all.
+pub fn leaf_diff_lines<T, F>(
+    old: &T,
+    new: &T,
+    format: &F,
+    ctx: &Context,
+) -> Result<Vec<DiffLine>, std::fmt::Error>
+where
+    T: ColorDisplay<F> + PartialEq + ?Sized,
+{
+    if old == new {
+        return render_lines(old, format, ctx, Change::Unchanged);
+    }
+    let mut lines = render_lines(old, format, ctx, Change::Removed)?;
+    lines.extend(render_lines(new, format, ctx, Change::Added)?);
+    Ok(lines)
+}
+
+/// Combine the diff of a nested value with the `header` that precedes the
+/// value, e.g. its key in a map.  When the nested diff is just a removed and
+/// an added line, the `old` and `new` renderings of the value together with
+/// its header are used instead.  Otherwise the header is written unchanged,
+/// and the nested diff follows on lines of its own.
+fn with_header(
+    header: &str,
+    nested: Vec<DiffLine>,
+    old: Vec<DiffLine>,
+    new: Vec<DiffLine>,
+) -> Vec<DiffLine> {
+    let is_changed_leaf = matches!(
+        nested.as_slice(),
+        [removed, added] if removed.change == Change::Removed
+            && added.change == Change::Added
+    );
+    if is_changed_leaf {
+        return old.into_iter().chain(new).collect();
+    }
+    let header = DiffLine {
+        change: Change::Unchanged,
+        text: header.trim_end().to_string(),
+    };
+    std::iter::once(header).chain(nested).collect()
+}
+
+macro_rules! impl_ColorDiff_for_leaf_types {
+    ($($type:ty => $format:ty),* $(,)?) => {
+        $(
+            impl ColorDiff<$format> for $type {
+                fn diff_lines(
+                    &self,
+                    other: &Self,
+                    format: &$format,
+                    ctx: &Context,
+                ) -> Result<Vec<DiffLine>, std::fmt::Error> {
+                    leaf_diff_lines(self, other, format, ctx)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_ColorDiff_for_numeric_types {
+    ($($type:ty),* $(,)?) => { paste::paste! {
+        impl_ColorDiff_for_leaf_types! {
+            $($type => crate::primitive::[<$type:camel Format>]),*
+        }
+    }}
+}
+
+impl_ColorDiff_for_numeric_types! {
+    i8, i16, i32, i64, i128, isize,
+    u8, u16, u32, u64, u128, usize,
+    f32, f64,
+}
+
+impl_ColorDiff_for_leaf_types! {
+    bool => BoolFormat,
+    char => CharFormat,
+    &str => StrFormat,
+    String => StrFormat,
+}
+
+/// An edit that turns one sequence into another.
+enum Edit {
+    Keep(usize),
+    Remove(usize),
+    Add(usize),
+}
+
+/// The edits that turn `old` into `new`, keeping the items of a longest
+/// common subsequence.  This is Myers' diff algorithm in linear space, so
+/// it takes O((N + M) D) time for D edits, and O(N + M) space.
+fn edits<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    let mut edits = Vec::with_capacity(old.len() + new.len());
+    push_edits(old, new, 0..old.len(), 0..new.len(), &mut edits);
+    edits
+}
+
+/// Push the edits that turn `old[old_range]` into `new[new_range]`.
+fn push_edits<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    mut old_range: Range<usize>,
+    mut new_range: Range<usize>,
+    edits: &mut Vec<Edit>,
+) {
+    while !old_range.is_empty() && !new_range.is_empty()
+        && old[old_range.start] == new[new_range.start]
+    {
+        edits.push(Edit::Keep(old_range.start));
+        old_range.start += 1;
+        new_range.start += 1;
+    }
+    let old_end = old_range.end;
+    while !old_range.is_empty() && !new_range.is_empty()
+        && old[old_range.end - 1] == new[new_range.end - 1]
+    {
+        old_range.end -= 1;
+        new_range.end -= 1;
+    }
+    if old_range.is_empty() {
+        edits.extend(new_range.map(Edit::Add));
+    } else if new_range.is_empty() {
+        edits.extend(old_range.clone().map(Edit::Remove));
+    } else {
+        let (x, y) = middle(&old[old_range.clone()], &new[new_range.clone()]);
+        let (x, y) = (old_range.start + x, new_range.start + y);
+        push_edits(old, new, old_range.start..x, new_range.start..y, edits);
+        push_edits(old, new, x..old_range.end, y..new_range.end, edits);
+    }
+    edits.extend((old_range.end..old_end).map(Edit::Keep));
+}
+
+/// A point `(x, y)` halfway along a shortest edit path from `old` to `new`,
+/// which are non-empty and differ in their first and last items, found by
+/// searching from both ends at once.  The edits of `old[..x]` to `new[..y]`
+/// and those of `old[x..]` to `new[y..]` make up the edits of the whole.
+fn middle<T: PartialEq>(old: &[T], new: &[T]) -> (usize, usize) {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d;
+    // The furthest `x` reached on each diagonal `k = x - y`, from the start
+    // and, in reverse, from the end.
+    let mut forward = vec![-1; 2 * max_d as usize + 2];
+    let mut backward = forward.clone();
+    forward[offset as usize + 1] = 0;
+    backward[offset as usize + 1] = 0;
+    let diagonals = forward.len() as isize;
+    let on_diagonal = |k: isize| (0..diagonals).contains(&(offset + k));
+    let delta = n - m;
+    // The paths meet on the forward pass if `delta` is odd.
+    let meet_forward = delta % 2 != 0;
+    // Diagonals that have left the edit graph are skipped.
+    let (mut k1_start, mut k1_end, mut k2_start, mut k2_end) = (0, 0, 0, 0);
+    for d in 0..max_d {
+        for k1 in (-d + k1_start..=d - k1_end).step_by(2) {
+            let idx = (offset + k1) as usize;
+            let mut x1 = match k1 == -d || (k1 != d && forward[idx - 1] < forward[idx + 1]) {
+                true => forward[idx + 1],
+                false => forward[idx - 1] + 1,
+            };
+            let mut y1 = x1 - k1;
+            while x1 < n && y1 < m && old[x1 as usize] == new[y1 as usize] {
+                x1 += 1;
+                y1 += 1;
+            }
+            forward[idx] = x1;
+            if x1 > n {
+                k1_end += 2;
+            } else if y1 > m {
+                k1_start += 2;
+            } else if meet_forward && on_diagonal(delta - k1) {
+                let x2 = backward[(offset + delta - k1) as usize];
+                if x2 != -1 && x1 >= n - x2 {
+                    return (x1 as usize, y1 as usize);
+                }
+            }
+        }
+        for k2 in (-d + k2_start..=d - k2_end).step_by(2) {
+            let idx = (offset + k2) as usize;
+            let mut x2 = match k2 == -d || (k2 != d && backward[idx - 1] < backward[idx + 1]) {
+                true => backward[idx + 1],
+                false => backward[idx - 1] + 1,
+            };
+            let mut y2 = x2 - k2;
+            while x2 < n && y2 < m
+                && old[(n - x2 - 1) as usize] == new[(m - y2 - 1) as usize]
+            {
+                x2 += 1;
+                y2 += 1;
+            }
+            backward[idx] = x2;
+            if x2 > n {
+                k2_end += 2;
+            } else if y2 > m {
+                k2_start += 2;
+            } else if !meet_forward && on_diagonal(delta - k2) {
+                let x1 = forward[(offset + delta - k2) as usize];
+                if x1 != -1 && x1 >= n - x2 {
+                    return (x1 as usize, (x1 - delta + k2) as usize);
+                }
+            }
+        }
+    }
+    // The paths don't meet if there's nothing in common at all.
+    (old.len(), 0)
+}
+
+/// The diff of the items of two sequences.  Items that are removed and added
+/// at the same position are paired up and diffed recursively.
+fn sequence_diff_lines<T, F>(
+    old: &[T],
+    new: &[T],
+    item_format: &F,
+    ctx: &Context,
+) -> Result<Vec<DiffLine>, std::fmt::Error>
+where
+    T: ColorDiff<F> + PartialEq,
+{
+    let ctx = Context { depth: ctx.depth + 1, ..ctx.clone() };
+    let mut lines = vec![];
+    let mut removed = vec![];
+    let mut added = vec![];
+    let flush = |lines: &mut Vec<DiffLine>, removed: &mut Vec<&T>, added: &mut Vec<&T>| {
+        let paired = removed.len().min(added.len());
+        for (old, new) in removed.iter().zip(added.iter()) {
+            lines.extend(old.diff_lines(new, item_format, &ctx)?);
+        }
+        for old in &removed[paired..] {
+            lines.extend(render_lines(*old, item_format, &ctx, Change::Removed)?);
+        }
+        for new in &added[paired..] {
+            lines.extend(render_lines(*new, item_format, &ctx, Change::Added)?);
+        }
+        removed.clear();
+        added.clear();
+        Ok::<_, std::fmt::Error>(())
+    };
+    for edit in edits(old, new) {
+        match edit {
+            Edit::Keep(idx) => {
+                flush(&mut lines, &mut removed, &mut added)?;
+                let item = &old[idx];
+                lines.extend(render_lines(item, item_format, &ctx, Change::Unchanged)?);
+            },
+            Edit::Remove(idx) => removed.push(&old[idx]),
+            Edit::Add(idx) => added.push(&new[idx]),
+        }
+    }
+    flush(&mut lines, &mut removed, &mut added)?;
+    Ok(lines)
+}
+
+impl<TF, T> ColorDiff<SliceFormat<TF>> for [T]
+where
+    T: ColorDiff<TF> + PartialEq,
+{
+    fn diff_lines(
+        &self,
+        other: &Self,
+        format: &SliceFormat<TF>,
+        ctx: &Context,
+    ) -> Result<Vec<DiffLine>, std::fmt::Error> {
+        sequence_diff_lines(self, other, &format.item_format, ctx)
+    }
+}
+
+impl<TF, T> ColorDiff<VecFormat<TF>> for Vec<T>
+where
+    T: ColorDiff<TF> + PartialEq,
+    TF: Clone,
+{
+    fn diff_lines(
+        &self,
+        other: &Self,
+        format: &VecFormat<TF>,
+        ctx: &Context,
+    ) -> Result<Vec<DiffLine>, std::fmt::Error> {
+        let format = format.to_slice_format();
+        self.as_slice().diff_lines(other.as_slice(), &format, ctx)
+    }
+}
+
+/// The parts of the format of a map that are needed to diff its entries.
+struct EntryFormat<'f, KF, VF> {
+    key_format: &'f KF,
+    key_value_separator: &'f str,
+    key_value_separator_style_desc: Option<StyleDesc>,
+    value_format: &'f VF,
+}
+
+/// The diff of the entries of two maps with the given `keys`, where `old`
+/// and `new` look up the value of a key in either map.
+fn entries_diff_lines<'m, K, V, KF, VF>(
+    keys: Vec<&'m K>,
+    old: impl Fn(&K) -> Option<&'m V>,
+    new: impl Fn(&K) -> Option<&'m V>,
+    format: EntryFormat<KF, VF>,
+    ctx: &Context,
+) -> Result<Vec<DiffLine>, std::fmt::Error>
+where
+    K: ColorDisplay<KF> + 'm,
+    V: ColorDiff<VF> + PartialEq + 'm,
+{
+    let ctx = Context { depth: ctx.depth + 1, column: 0, ..ctx.clone() };
+    // The separator is trimmed at the end of a line, i.e. if the value is
+    // left out, or starts on a line of its own like a collection can.
+    let render_entry = |key: &K, value: Option<&V>, change| {
+        let mut ctx = ctx.clone();
+        let mut rendered = String::new();
+        key.color_fmt_in(&mut rendered, format.key_format, &mut ctx)?;
+        let separator = format.key_value_separator;
+        let style_desc = format.key_value_separator_style_desc;
+        let mut value_ctx = ctx.clone();
+        let mut value_text = String::new();
+        value_ctx.write(&mut value_text, &ctx.paint(separator, style_desc))?;
+        let value_start = value_text.len();
+        if let Some(value) = value {
+            value.color_fmt_in(&mut value_text, format.value_format, &mut value_ctx)?;
+        }
+        let value_text = &value_text[value_start..];
+        let newline = ctx.newline_token_or("\n");
+        let separator = match value_text.is_empty() || value_text.starts_with(newline) {
+            true => separator.trim_end(),
+            false => separator,
+        };
+        rendered.push_str(&ctx.paint(separator, style_desc));
+        rendered.push_str(value_text);
+        Ok::<_, std::fmt::Error>(split_lines(&rendered, change))
+    };
+    let mut lines = vec![];
+    for key in keys {
+        match (old(key), new(key)) {
+            (Some(old), Some(new)) if old == new => {
+                lines.extend(render_entry(key, Some(old), Change::Unchanged)?);
+            },
+            (Some(old), Some(new)) => {
+                let nested = old.diff_lines(new, format.value_format, &ctx)?;
+                let header = render_entry(key, None, Change::Unchanged)?;
+                let header = header.last().map_or("", |line| line.text.as_str());
+                lines.extend(with_header(
+                    header,
+                    nested,
+                    render_entry(key, Some(old), Change::Removed)?,
+                    render_entry(key, Some(new), Change::Added)?,
+                ));
+            },
+            (Some(old), None) => {
+                lines.extend(render_entry(key, Some(old), Change::Removed)?);
+            },
+            (None, Some(new)) => {
+                lines.extend(render_entry(key, Some(new), Change::Added)?);
+            },
+            (None, None) => {},
+        }
+    }
+    Ok(lines)
+}
+
+impl<KF, VF, K, V> ColorDiff<BTreeMapFormat<KF, VF>> for BTreeMap<K, V>
+where
+    K: ColorDisplay<KF> + Ord,
+    V: ColorDiff<VF> + PartialEq,
+{
+    fn diff_lines(
+        &self,
+        other: &Self,
+        format: &BTreeMapFormat<KF, VF>,
+        ctx: &Context,
+    ) -> Result<Vec<DiffLine>, std::fmt::Error> {
+        let keys: BTreeSet<&K> = self.keys().chain(other.keys()).collect();
+        entries_diff_lines(
+            keys.into_iter().collect(),
+            |key| self.get(key),
+            |key| other.get(key),
+            EntryFormat {
+                key_format: &format.key_format,
+                key_value_separator: &format.key_value_separator,
+                key_value_separator_style_desc: format.key_value_separator_style_desc,
+                value_format: &format.value_format,
+            },
+            ctx,
+        )
+    }
+}
+
+/// The entries of hash maps are ordered by `format.order`, or by their
+/// rendered keys if that is `Order::Iteration`, so that diffs are stable.
+impl<KF, VF, K, V> ColorDiff<HashMapFormat<KF, VF, K>> for HashMap<K, V>
+where
+    K: ColorDisplay<KF> + Eq + Hash,
+    V: ColorDiff<VF> + PartialEq,
+{
+    fn diff_lines(
+        &self,
+        other: &Self,
+        format: &HashMapFormat<KF, VF, K>,
+        ctx: &Context,
+    ) -> Result<Vec<DiffLine>, std::fmt::Error> {
+        let keys: HashSet<&K> = self.keys().chain(other.keys()).collect();
+        let mut keys: Vec<&K> = keys.into_iter().collect();
+        let order = match &format.order {
+            Order::Iteration => &Order::Rendered,
+            order => order,
+        };
+        sort(&mut keys, order, &format.key_format, |key| *key);
+        entries_diff_lines(
+            keys,
+            |key| self.get(key),
+            |key| other.get(key),
+            EntryFormat {
+                key_format: &format.key_format,
+                key_value_separator: &format.key_value_separator,
+                key_value_separator_style_desc: format.key_value_separator_style_desc,
+                value_format: &format.value_format,
+            },
+            ctx,
+        )
+    }
+}
+
+/// The diff of the `items` of two sets, where `in_old` and `in_new` tell
+/// whether an item is part of either set.
+fn items_diff_lines<T, F>(
+    items: Vec<&T>,
+    in_old: impl Fn(&T) -> bool,
+    in_new: impl Fn(&T) -> bool,
+    item_format: &F,
+    ctx: &Context,
+) -> Result<Vec<DiffLine>, std::fmt::Error>
+where
+    T: ColorDisplay<F>,
+{
+    let ctx = Context { depth: ctx.depth + 1, ..ctx.clone() };
+    let mut lines = vec![];
+    for item in items {
+        let change = match (in_old(item), in_new(item)) {
+            (true, true) => Change::Unchanged,
+            (true, false) => Change::Removed,
+            _ => Change::Added,
+        };
+        lines.extend(render_lines(item, item_format, &ctx, change)?);
+    }
+    Ok(lines)
+}
+
+impl<TF, T> ColorDiff<BTreeSetFormat<TF>> for BTreeSet<T>
+where
+    T: ColorDisplay<TF> + Ord,
+{
+    fn diff_lines(
+        &self,
+        other: &Self,
+        format: &BTreeSetFormat<TF>,
+        ctx: &Context,
+    ) -> Result<Vec<DiffLine>, std::fmt::Error> {
+        let items: BTreeSet<&T> = self.union(other).collect();
+        items_diff_lines(
+            items.into_iter().collect(),
+            |item| self.contains(item),
+            |item| other.contains(item),
+            &format.item_format,
+            ctx,
+        )
+    }
+}
+
+/// The items of hash sets are ordered by `format.order`, or by their
+/// rendered text if that is `Order::Iteration`, so that diffs are stable.
+impl<TF, T> ColorDiff<HashSetFormat<TF, T>> for HashSet<T>
+where
+    T: ColorDisplay<TF> + Eq + Hash,
+{
+    fn diff_lines(
+        &self,
+        other: &Self,
+        format: &HashSetFormat<TF, T>,
+        ctx: &Context,
+    ) -> Result<Vec<DiffLine>, std::fmt::Error> {
+        let mut items: Vec<&T> = self.union(other).collect();
+        let order = match &format.order {
+            Order::Iteration => &Order::Rendered,
+            order => order,
+        };
+        sort(&mut items, order, &format.item_format, |item| *item);
+        items_diff_lines(
+            items,
+            |item| self.contains(item),
+            |item| other.contains(item),
+            &format.item_format,
+            ctx,
+        )
+    }
+}
+
+impl<TF, T> ColorDiff<OptionFormat<TF>> for Option<T>
+where
+    T: ColorDiff<TF> + PartialEq,
+{
+    fn diff_lines(
+        &self,
+        other: &Self,
+        format: &OptionFormat<TF>,
+        ctx: &Context,
+    ) -> Result<Vec<DiffLine>, std::fmt::Error> {
+        match (self, other) {
+            (Some(old), Some(new)) if old != new => {
+                let nested = old.diff_lines(new, &format.some_format, ctx)?;
+                if format.some_prefix.is_empty() {
+                    return Ok(nested);
+                }
+                Ok(with_header(
+                    &format.some_prefix,
+                    nested,
+                    render_lines(self, format, ctx, Change::Removed)?,
+                    render_lines(other, format, ctx, Change::Added)?,
+                ))
+            },
+            _ => leaf_diff_lines(self, other, format, ctx),
+        }
+    }
+}
+
+impl<OF, EF, O, E> ColorDiff<ResultFormat<OF, EF>> for Result<O, E>
+where
+    O: ColorDiff<OF> + PartialEq,
+    E: ColorDiff<EF> + PartialEq,
+{
+    fn diff_lines(
+        &self,
+        other: &Self,
+        format: &ResultFormat<OF, EF>,
+        ctx: &Context,
+    ) -> Result<Vec<DiffLine>, std::fmt::Error> {
+        let (prefix, suffix, nested) = match (self, other) {
+            (Ok(old), Ok(new)) if old != new => (
+                &format.ok_prefix,
+                &format.ok_suffix,
+                old.diff_lines(new, &format.ok_format, ctx)?,
+            ),
+            (Err(old), Err(new)) if old != new => (
+                &format.err_prefix,
+                &format.err_suffix,
+                old.diff_lines(new, &format.err_format, ctx)?,
+            ),
+            _ => return leaf_diff_lines(self, other, format, ctx),
+        };
+        if prefix.is_empty() && suffix.is_empty() {
+            return Ok(nested);
+        }
+        let mut lines = with_header(
+            prefix,
+            nested,
+            render_lines(self, format, ctx, Change::Removed)?,
+            render_lines(other, format, ctx, Change::Added)?,
+        );
+        // A nested diff under the prefix is closed by the suffix, e.g. the
+        // `)` of `Ok(..)`.
+        if !suffix.is_empty() && lines[0].change == Change::Unchanged {
+            lines.push(DiffLine { change: Change::Unchanged, text: suffix.to_string() });
+        }
+        Ok(lines)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        layout::{Layout, Truncation},
+        primitive::{U32Format, U8Format},
+        result::ResultMarkers,
+        Color, Format, StyleOverlay,
+    };
+    use super::*;
+
+    #[test]
+    fn sequence_color_diff() -> std::fmt::Result {
+        let old: Vec<u8> = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let new: Vec<u8> = vec![1, 2, 3, 4, 50, 6, 7, 8, 9, 10];
+        let mut sink = String::with_capacity(1024);
+        old.as_slice().color_diff(
+            &new,
+            &mut sink,
+            &SliceFormat::<U8Format>::monochrome(0),
+            &DiffFormat { context: Some(1), ..DiffFormat::monochrome() },
+        )?;
+        let expected = concat!(
+            "  … 3 unchanged\n",
+            "  4\n",
+            "- 5\n",
+            "+ 50\n",
+            "  6\n",
+            "  … 2 unchanged\n",
+            "  9\n",
+            "+ 10",
+        );
+        assert_eq!(sink, expected);
+
+        let mut sink = String::with_capacity(1024);
+        [1u8, 2].color_diff(
+            &[2, 3],
+            &mut sink,
+            &SliceFormat::<U8Format>::colored(0),
+            &DiffFormat::colored(),
+        )?;
+        let expected = format!(
+            "{}\n  {}\n{}",
+            Color::Red.paint("- 1"),
+            Color::Blue.bold().paint("2"),
+            Color::Green.paint("+ 3"),
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn large_sequence_color_diff() -> std::fmt::Result {
+        let old: Vec<u32> = (0..50_000).collect();
+        let mut new = old.clone();
+        new[25_000] = 0;
+        let format = SliceFormat::<U32Format>::monochrome(0);
+        let diff_format = DiffFormat { context: Some(1), ..DiffFormat::monochrome() };
+        let mut sink = String::with_capacity(1024);
+        old.as_slice().color_diff(&new, &mut sink, &format, &diff_format)?;
+        let expected = concat!(
+            "  … 24999 unchanged\n",
+            "  24999\n",
+            "- 25000\n",
+            "+ 0\n",
+            "  25001\n",
+            "  … 24998 unchanged",
+        );
+        assert_eq!(sink, expected);
+
+        // Without a common prefix or suffix.
+        new[0] = 50_000;
+        new[49_999] = 50_001;
+        let mut sink = String::with_capacity(1024);
+        old.as_slice().color_diff(&new, &mut sink, &format, &diff_format)?;
+        assert!(sink.starts_with("- 0\n+ 50000\n  1\n"));
+        assert!(sink.ends_with("  49998\n- 49999\n+ 50001"));
+        Ok(())
+    }
+
+    #[test]
+    fn nested_color_diff() -> std::fmt::Result {
+        let mut old: BTreeMap<&str, Vec<u8>> = BTreeMap::new();
+        old.insert("a", vec![1, 2, 3]);
+        old.insert("b", vec![4]);
+        old.insert("c", vec![5]);
+        let mut new = old.clone();
+        new.insert("a", vec![1, 3]);
+        new.remove("b");
+        new.insert("d", vec![]);
+        let mut sink = String::with_capacity(1024);
+        old.color_diff(&new, &mut sink, &BTreeMapFormat {
+            prefix_newlines: 0,
+            intersperse_newlines: 1,
+            suffix_newlines: 0,
+            key_value_separator: ": ".into(),
+            key_value_separator_style_desc: None,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(0),
+            style_overlay: StyleOverlay::INHERIT,
+            key_alignment: None,
+            key_format: StrFormat::monochrome(0),
+            value_format: VecFormat {
+                prefix_newlines: 1,
+                intersperse_newlines: 1,
+                suffix_newlines: 0,
+                layout: Layout::Lines,
+                truncation: Truncation::monochrome(0),
+                style_overlay: StyleOverlay::INHERIT,
+                item_format: U8Format::monochrome(0),
+            },
+        }, &DiffFormat { context: None, ..DiffFormat::monochrome() })?;
+        let expected = concat!(
+            "  a:\n",
+            "      1\n",
+            "-     2\n",
+            "      3\n",
+            "- b:\n",
+            "-     4\n",
+            "  c:\n",
+            "      5\n",
+            "+ d:",
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn option_and_result_color_diff() -> std::fmt::Result {
+        let format = OptionFormat::<U8Format>::monochrome(0);
+        let mut sink = String::with_capacity(1024);
+        Some(1u8).color_diff(&None, &mut sink, &format, &DiffFormat::monochrome())?;
+        assert_eq!(sink, "- 1\n+ None");
+
+        let format = ResultFormat::<StrFormat, StrFormat>::monochrome(0)
+            .with_markers(ResultMarkers::Emoji);
+        let old: Result<&str, &str> = Ok("yes");
+        let mut sink = String::with_capacity(1024);
+        old.color_diff(&Ok("no"), &mut sink, &format, &DiffFormat::monochrome())?;
+        assert_eq!(sink, "- ✅ yes\n+ ✅ no");
+        Ok(())
+    }
+
+    #[test]
+    fn set_color_diff() -> std::fmt::Result {
+        let old: HashSet<u8> = [3, 1, 2].into_iter().collect();
+        let new: HashSet<u8> = [4, 2, 3].into_iter().collect();
+        let mut sink = String::with_capacity(1024);
+        old.color_diff(
+            &new,
+            &mut sink,
+            &HashSetFormat::<U8Format, _>::monochrome(0),
+            &DiffFormat::monochrome(),
+        )?;
+        assert_eq!(sink, "- 1\n  2\n  3\n+ 4");
+        Ok(())
+    }
+}
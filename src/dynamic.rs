@@ -0,0 +1,153 @@
+//! Rendering values of different types, each with its own format, through
+//! trait objects, e.g. the items of a `Vec<Box<dyn DynColorDisplay>>`.
+
+use crate::{ColorDisplay, Context, Format};
+use std::fmt::Write;
+
+/// An object-safe companion of `ColorDisplay`, for values that carry their
+/// format with them, see `Formatted`.
+pub trait DynColorDisplay {
+    fn dyn_color_fmt(&self, sink: &mut dyn Write) -> std::fmt::Result;
+
+    /// Like `dyn_color_fmt()`, but as part of a larger rendering described
+    /// by `ctx`, see `ColorDisplay::color_fmt_in()`.
+    fn dyn_color_fmt_in(
+        &self,
+        sink: &mut dyn Write,
+        ctx: &mut Context,
+    ) -> std::fmt::Result;
+}
+
+/// A value paired with the format to render it with.
+#[derive(Clone, Copy)]
+pub struct Formatted<T, F> {
+    pub value: T,
+    pub format: F,
+}
+
+impl<T, F> Formatted<T, F> {
+    pub fn new(value: T, format: F) -> Self {
+        Self { value, format }
+    }
+}
+
+impl<T, F> Formatted<T, F>
+where
+    T: ColorDisplay<F> + 'static,
+    F: 'static,
+{
+    pub fn boxed(value: T, format: F) -> Box<dyn DynColorDisplay> {
+        Box::new(Self::new(value, format))
+    }
+}
+
+impl<T, F> DynColorDisplay for Formatted<T, F>
+where
+    T: ColorDisplay<F>,
+{
+    fn dyn_color_fmt(&self, mut sink: &mut dyn Write) -> std::fmt::Result {
+        self.value.color_fmt(&mut sink, &self.format)
+    }
+
+    fn dyn_color_fmt_in(
+        &self,
+        mut sink: &mut dyn Write,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        self.value.color_fmt_in(&mut sink, &self.format, ctx)
+    }
+}
+
+/// The format of values that carry their own format, i.e. of trait objects
+/// of `DynColorDisplay`.  Collections of them use it as their item format.
+#[derive(Clone, Copy, Default)]
+pub struct DynFormat;
+
+impl Format for DynFormat {
+    fn colored(_: u16) -> Self {
+        Self
+    }
+
+    fn monochrome(_: u16) -> Self {
+        Self
+    }
+}
+
+impl ColorDisplay<DynFormat> for dyn DynColorDisplay + '_ {
+    fn color_fmt(
+        &self,
+        sink: &mut impl Write,
+        _: &DynFormat,
+    ) -> std::fmt::Result {
+        self.dyn_color_fmt(sink)
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        _: &DynFormat,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        self.dyn_color_fmt_in(sink, ctx)
+    }
+}
+
+impl ColorDisplay<DynFormat> for Box<dyn DynColorDisplay + '_> {
+    fn color_fmt(
+        &self,
+        sink: &mut impl Write,
+        format: &DynFormat,
+    ) -> std::fmt::Result {
+        (**self).color_fmt(sink, format)
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &DynFormat,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        (**self).color_fmt_in(sink, format, ctx)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        bool::BoolFormat,
+        primitive::U8Format,
+        str::StrFormat,
+        vec::VecFormat,
+        Color,
+    };
+    use super::*;
+
+    #[test]
+    fn heterogeneous_color_fmt() -> std::fmt::Result {
+        let values: Vec<Box<dyn DynColorDisplay>> = vec![
+            Formatted::boxed(7u8, U8Format::colored(0)),
+            Formatted::boxed("seven", StrFormat::monochrome(0)),
+            Formatted::boxed(true, BoolFormat::monochrome(0)),
+        ];
+        let mut sink = String::with_capacity(1024);
+        values.color_fmt(&mut sink, &VecFormat::<DynFormat>::monochrome(0))?;
+        assert_eq!(sink, format!("{}\nseven\ntrue", Color::Blue.bold().paint("7")));
+
+        let mut sink = String::with_capacity(1024);
+        values.color_fmt(&mut sink, &VecFormat::<DynFormat>::monochrome(0).inline())?;
+        assert_eq!(sink, format!("{}, seven, true", Color::Blue.bold().paint("7")));
+        Ok(())
+    }
+
+    #[test]
+    fn borrowed_dyn_color_fmt() -> std::fmt::Result {
+        let name = String::from("seven");
+        let formatted = Formatted::new(name.as_str(), StrFormat::monochrome(0));
+        let value: &dyn DynColorDisplay = &formatted;
+        let mut sink = String::with_capacity(1024);
+        value.dyn_color_fmt(&mut sink)?;
+        assert_eq!(sink, "seven");
+        Ok(())
+    }
+}
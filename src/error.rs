@@ -0,0 +1,338 @@
+//! `ColorDisplay` support for errors: `dyn Error` with its chain of sources,
+//! and `ErrorReport`, which adds a backtrace.
+
+use crate::{ansi, Color, ColorDisplay, Context, Format, StyleDesc, Text};
+use crate::indent::IndentWriter;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::error::Error;
+use std::fmt::Write;
+
+impl ColorDisplay<ErrorFormat> for dyn Error + '_ {
+    fn color_fmt(
+        &self,
+        sink: &mut impl Write,
+        format: &ErrorFormat,
+    ) -> std::fmt::Result {
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &ErrorFormat,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        ErrorReport { error: self, backtrace: None }.color_fmt_in(sink, format, ctx)
+    }
+}
+
+/// An error together with the backtrace captured where it occurred.
+#[derive(Clone, Copy)]
+pub struct ErrorReport<'e> {
+    pub error: &'e dyn Error,
+    /// Written after the chain of sources if it was captured, see
+    /// `Backtrace::capture()`.
+    pub backtrace: Option<&'e Backtrace>,
+}
+
+impl ColorDisplay<ErrorFormat> for ErrorReport<'_> {
+    fn color_fmt(
+        &self,
+        sink: &mut impl Write,
+        format: &ErrorFormat,
+    ) -> std::fmt::Result {
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &ErrorFormat,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        let indent = ctx.indentation(format.indent);
+        self.write_indentation_in(sink, indent, format, ctx)?;
+        let token = ctx.indent_token_or(Self::INDENTATION).to_string();
+        let message = self.error.to_string();
+        let mut text = ctx.leaf_style(format.style_desc).paint(&message).to_string();
+
+        let causes = causes(self.error, &message, format.dedupe);
+        if !causes.is_empty() {
+            text.push_str("\n\n");
+            text.push_str(&ctx.paint(&format.caused_by, format.caused_by_style_desc));
+            let style = ctx.leaf_style(format.cause_style_desc);
+            let width = (causes.len() - 1).to_string().len();
+            for (idx, cause) in causes.iter().enumerate() {
+                // The lines of a multi-line cause are aligned with its first.
+                let number = format!("{idx:>width$}: ");
+                let alignment = " ".repeat(ansi::visible_width(&token) + number.len());
+                text.push('\n');
+                text.push_str(&token);
+                text.push_str(&ctx.paint(&number, format.number_style_desc));
+                let mut writer = IndentWriter::new(&mut text, alignment);
+                writer.write_str(&style.paint(cause.as_str()).to_string())?;
+                writer.finish()?;
+            }
+        }
+
+        if let Some(backtrace) = self.backtrace {
+            if backtrace.status() == BacktraceStatus::Captured {
+                text.push_str("\n\n");
+                let header = &format.backtrace_header;
+                text.push_str(&ctx.paint(header, format.caused_by_style_desc));
+                text.push('\n');
+                text.push_str(&render_backtrace(backtrace, format, ctx));
+            }
+        }
+
+        let continuation = ctx.continuation(format.indent, &token);
+        let mut out = String::with_capacity(text.len() + 16);
+        let mut writer = IndentWriter::new(&mut out, continuation)
+            .with_newline(ctx.newline_token_or(Self::NEWLINE));
+        writer.write_str(&text)?;
+        writer.finish()?;
+        ctx.write(sink, &out)
+    }
+}
+
+/// The messages of the sources of `error`, whose own message is `message`.
+/// With `dedupe`, messages that were already written are skipped, as are
+/// messages that the previous message ends with after a `": "`, as in
+/// `"reading: denied"` caused by `"denied"`.
+fn causes(error: &dyn Error, message: &str, dedupe: bool) -> Vec<String> {
+    let mut written = vec![message.to_string()];
+    let mut causes = vec![];
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let cause_message = cause.to_string();
+        let is_repeated = written.contains(&cause_message)
+            || written.last().is_some_and(|last| {
+                last.ends_with(&format!(": {cause_message}"))
+            });
+        if !(dedupe && is_repeated) {
+            causes.push(cause_message.clone());
+        }
+        written.push(cause_message);
+        source = cause.source();
+    }
+    causes
+}
+
+/// The frames of `backtrace`, with the frames of `format.own_crates` and
+/// their locations painted in `format.own_frame_style_desc`.
+fn render_backtrace(
+    backtrace: &Backtrace,
+    format: &ErrorFormat,
+    ctx: &Context,
+) -> String {
+    let frame_style = ctx.leaf_style(format.frame_style_desc);
+    let own_frame_style = ctx.leaf_style(format.own_frame_style_desc);
+    let is_own = |symbol: &str| format.own_crates.iter().any(|krate| {
+        symbol.strip_prefix(krate.as_ref()).is_some_and(|rest| rest.starts_with("::"))
+    });
+    let mut own = false;
+    let lines: Vec<String> = backtrace.to_string().lines()
+        .map(|line| {
+            // Frames are written as `  3: symbol`, followed by their
+            // location on a line of its own, as in `      at src/lib.rs:1:2`.
+            let trimmed = line.trim_start();
+            if let Some((number, symbol)) = trimmed.split_once(": ") {
+                if number.bytes().all(|b| b.is_ascii_digit()) {
+                    own = is_own(symbol);
+                }
+            }
+            let style = if own { own_frame_style } else { frame_style };
+            style.paint(line).to_string()
+        })
+        .collect();
+    lines.join("\n")
+}
+
+#[derive(Clone)]
+pub struct ErrorFormat {
+    pub indent: u16,
+    /// The style of the message of the error itself.
+    pub style_desc: Option<StyleDesc>,
+    /// The header of the list of sources.
+    pub caused_by: Text,
+    /// The style of the headers of the sources and of the backtrace.
+    pub caused_by_style_desc: Option<StyleDesc>,
+    pub number_style_desc: Option<StyleDesc>,
+    /// The style of the messages of the sources.
+    pub cause_style_desc: Option<StyleDesc>,
+    /// Whether to skip the messages of sources that were already written.
+    pub dedupe: bool,
+    pub backtrace_header: Text,
+    pub frame_style_desc: Option<StyleDesc>,
+    /// The names of the crates whose frames are highlighted in backtraces,
+    /// e.g. `"my_app"`.
+    pub own_crates: Vec<Text>,
+    pub own_frame_style_desc: Option<StyleDesc>,
+}
+
+impl ErrorFormat {
+    /// Highlight the frames of `own_crates` in backtraces.
+    pub fn with_own_crates<T>(self, own_crates: impl IntoIterator<Item = T>) -> Self
+    where
+        T: Into<Text>,
+    {
+        Self {
+            own_crates: own_crates.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+}
+
+impl Format for ErrorFormat {
+    fn colored(indent: u16) -> Self {
+        Self {
+            indent,
+            style_desc: Some(StyleDesc::fg(Color::Red).bold()),
+            caused_by: "Caused by:".into(),
+            caused_by_style_desc: Some(StyleDesc::PLAIN.bold()),
+            number_style_desc: Some(StyleDesc::PUNCTUATION),
+            cause_style_desc: Some(StyleDesc::fg(Color::Yellow)),
+            dedupe: true,
+            backtrace_header: "Stack backtrace:".into(),
+            frame_style_desc: Some(StyleDesc::PLAIN.dimmed()),
+            own_crates: vec![],
+            own_frame_style_desc: Some(StyleDesc::fg(Color::Cyan).bold()),
+        }
+    }
+
+    fn monochrome(indent: u16) -> Self {
+        Self {
+            indent,
+            style_desc: None,
+            caused_by: "Caused by:".into(),
+            caused_by_style_desc: None,
+            number_style_desc: None,
+            cause_style_desc: None,
+            dedupe: true,
+            backtrace_header: "Stack backtrace:".into(),
+            frame_style_desc: None,
+            own_crates: vec![],
+            own_frame_style_desc: None,
+        }
+    }
+}
+
+impl Default for ErrorFormat {
+    fn default() -> Self {
+        Self::colored(0)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Chained(&'static str, Option<Box<Chained>>);
+
+    impl std::fmt::Display for Chained {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    impl Error for Chained {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            self.1.as_deref().map(|source| source as _)
+        }
+    }
+
+    fn chain(messages: &[&'static str]) -> Chained {
+        let (last, rest) = messages.split_last().unwrap();
+        rest.iter().rev().fold(Chained(last, None), |source, message| {
+            Chained(message, Some(Box::new(source)))
+        })
+    }
+
+    fn render(error: &dyn Error, format: &ErrorFormat) -> String {
+        let mut sink = String::with_capacity(1024);
+        error.color_fmt(&mut sink, format).unwrap();
+        sink
+    }
+
+    #[test]
+    fn color_fmt() {
+        let error = chain(&["loading config", "reading a.toml", "denied\nby policy"]);
+        let format = ErrorFormat::monochrome(0);
+        assert_eq!(
+            render(&error, &format),
+            "loading config\n\nCaused by:\n    0: reading a.toml\n    1: denied\n       by policy",
+        );
+
+        let error = chain(&["a", "b"]);
+        let expected = format!(
+            "{}\n\n{}\n    {}{}",
+            Color::Red.bold().paint("a"),
+            ansi_term::Style::new().bold().paint("Caused by:"),
+            Color::White.dimmed().paint("0: "),
+            Color::Yellow.paint("b"),
+        );
+        assert_eq!(render(&error, &ErrorFormat::colored(0)), expected);
+        assert_eq!(render(&chain(&["alone"]), &format), "alone");
+    }
+
+    #[test]
+    fn dedupe() {
+        let error = chain(&["reading: denied", "denied", "a", "b", "a", "c"]);
+        let format = ErrorFormat::monochrome(0);
+        assert_eq!(
+            render(&error, &format),
+            "reading: denied\n\nCaused by:\n    0: a\n    1: b\n    2: c",
+        );
+        let format = ErrorFormat { dedupe: false, ..format };
+        let rendered = render(&error, &format);
+        assert!(rendered.ends_with("    0: denied\n    1: a\n    2: b\n    3: a\n    4: c"));
+    }
+
+    #[test]
+    fn dedupe_suffix_without_separator() {
+        let format = ErrorFormat::monochrome(0);
+        let error = chain(&["bad data", "a"]);
+        assert_eq!(render(&error, &format), "bad data\n\nCaused by:\n    0: a");
+        let error = chain(&["failed to parse config", "config"]);
+        assert_eq!(
+            render(&error, &format),
+            "failed to parse config\n\nCaused by:\n    0: config",
+        );
+    }
+
+    #[test]
+    fn nested_color_fmt() -> std::fmt::Result {
+        let error = chain(&["outer", "inner"]);
+        let mut sink = String::from("error: ");
+        let mut ctx = Context { column: 7, ..Context::default() };
+        let error: &dyn Error = &error;
+        error.color_fmt_in(&mut sink, &ErrorFormat::monochrome(1), &mut ctx)?;
+        assert_eq!(sink, "error:     outer\n\n    Caused by:\n        0: inner");
+        Ok(())
+    }
+
+    #[test]
+    fn backtrace() -> std::fmt::Result {
+        let error = chain(&["failed"]);
+        // Not captured, so it isn't written.
+        let disabled = Backtrace::disabled();
+        let report = ErrorReport { error: &error, backtrace: Some(&disabled) };
+        let mut sink = String::with_capacity(1024);
+        report.color_fmt(&mut sink, &ErrorFormat::monochrome(0))?;
+        assert_eq!(sink, "failed");
+
+        let captured = Backtrace::force_capture();
+        let report = ErrorReport { error: &error, backtrace: Some(&captured) };
+        let format = ErrorFormat::colored(0).with_own_crates(["color_your_life"]);
+        let mut sink = String::with_capacity(1024);
+        report.color_fmt(&mut sink, &format)?;
+        let plain = ansi::strip_escapes(&sink);
+        assert!(plain.starts_with("failed\n\nStack backtrace:\n"));
+        assert!(plain.contains("color_your_life::error::test::backtrace"));
+        let own_frame = Color::Cyan.bold().prefix().to_string();
+        assert!(sink.contains(&own_frame));
+        Ok(())
+    }
+}
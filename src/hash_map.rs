@@ -1,9 +1,13 @@
-//!
+//! `ColorDisplay` support for `HashMap<K, V>`.
 
 use super::*;
+use crate::layout::{
+    elide, impl_collection_builders, sort, write_items, write_key,
+    CollectionFormat, KeyAlignment, Layout, Order, Truncation,
+};
 use std::collections::HashMap;
 
-impl<KF, VF, K, V> ColorDisplay<HashMapFormat<KF, VF>> for HashMap<K, V>
+impl<KF, VF, K, V> ColorDisplay<HashMapFormat<KF, VF, K>> for HashMap<K, V>
 where
     K: ColorDisplay<KF>,
     V: ColorDisplay<VF>,
@@ -11,40 +15,93 @@ where
     fn color_fmt(
         &self,
         sink: &mut impl Write,
-        format: &HashMapFormat<KF, VF>,
+        format: &HashMapFormat<KF, VF, K>,
     ) -> std::fmt::Result {
-        self.write_newlines(sink, format.prefix_newlines, &format)?;
-        for (idx, (key, value)) in self.iter().enumerate() {
-            if idx > 0 {
-                self.write_newlines(sink, format.intersperse_newlines, &format)?;
-            }
-            key.color_fmt(sink, &format.key_format)?;
-            write!(sink, "{}", format.key_value_separator)?;
-            value.color_fmt(sink, &format.value_format)?;
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
 
-        }
-        self.write_newlines(sink, format.suffix_newlines, &format)?;
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &HashMapFormat<KF, VF, K>,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        self.write_newlines_in(sink, format.prefix_newlines, format, ctx)?;
+        let mut entries: Vec<_> = self.iter().collect();
+        sort(&mut entries, &format.order, &format.key_format, |(key, _)| key);
+        let key_width = format.key_alignment.map(|alignment| {
+            alignment.width(entries.iter().map(|(key, _)| *key), &format.key_format)
+        }).transpose()?;
+        write_items(
+            self, sink, format, ctx,
+            elide(entries.into_iter(), format.truncation.limit),
+            |sink, ctx, (key, value)| {
+                let alignment = format.key_alignment.as_ref().zip(key_width);
+                write_key(sink, ctx, key, &format.key_format, alignment)?;
+                let separator = ctx.paint(
+                    &format.key_value_separator,
+                    format.key_value_separator_style_desc,
+                );
+                ctx.write(sink, &separator)?;
+                value.color_fmt_in(sink, &format.value_format, ctx)
+            },
+        )?;
+        self.write_newlines_in(sink, format.suffix_newlines, format, ctx)?;
         Ok(())
     }
 }
 
-#[derive(Clone, Copy)]
-pub struct HashMapFormat<KF, VF> {
+pub struct HashMapFormat<KF, VF, K> {
     pub prefix_newlines: u16,
     pub intersperse_newlines: u16,
     pub suffix_newlines: u16,
-    pub key_value_separator: &'static str,
+    pub key_value_separator: Text,
+    pub key_value_separator_style_desc: Option<StyleDesc>,
+    pub layout: Layout,
+    pub truncation: Truncation,
+    /// Layered over the styles of the descendants, see `StyleOverlay`.
+    pub style_overlay: StyleOverlay,
+    /// How to pad the keys, if at all.
+    pub key_alignment: Option<KeyAlignment>,
+    /// The order of the entries, by key.
+    pub order: Order<K>,
     pub key_format: KF,
     pub value_format: VF,
 }
 
-impl<KF: Format, VF: Format> Format for HashMapFormat<KF, VF> {
+impl<KF: Clone, VF: Clone, K> Clone for HashMapFormat<KF, VF, K> {
+    fn clone(&self) -> Self {
+        Self {
+            prefix_newlines: self.prefix_newlines,
+            intersperse_newlines: self.intersperse_newlines,
+            suffix_newlines: self.suffix_newlines,
+            key_value_separator: self.key_value_separator.clone(),
+            key_value_separator_style_desc: self.key_value_separator_style_desc,
+            layout: self.layout.clone(),
+            truncation: self.truncation,
+            style_overlay: self.style_overlay,
+            key_alignment: self.key_alignment,
+            order: self.order.clone(),
+            key_format: self.key_format.clone(),
+            value_format: self.value_format.clone(),
+        }
+    }
+}
+
+
+impl<KF: Format, VF: Format, K> Format for HashMapFormat<KF, VF, K> {
     fn colored(indent: u16) -> Self {
         Self {
             prefix_newlines: 0,
             intersperse_newlines: 1,
             suffix_newlines: 0,
-            key_value_separator: ": ",
+            key_value_separator: ": ".into(),
+            key_value_separator_style_desc: Some(StyleDesc::PUNCTUATION),
+            layout: Layout::Lines,
+            truncation: Truncation::colored(indent),
+            style_overlay: StyleOverlay::INHERIT,
+            key_alignment: None,
+            order: Order::Iteration,
             key_format: KF::colored(indent),
             value_format: VF::colored(indent),
         }
@@ -55,19 +112,51 @@ impl<KF: Format, VF: Format> Format for HashMapFormat<KF, VF> {
             prefix_newlines: 0,
             intersperse_newlines: 1,
             suffix_newlines: 0,
-            key_value_separator: ": ",
+            key_value_separator: ": ".into(),
+            key_value_separator_style_desc: None,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(indent),
+            style_overlay: StyleOverlay::INHERIT,
+            key_alignment: None,
+            order: Order::Iteration,
             key_format: KF::monochrome(indent),
             value_format: VF::monochrome(indent),
         }
     }
 }
 
+impl<KF: Format, VF: Format, K> Default for HashMapFormat<KF, VF, K> {
+    fn default() -> Self {
+        Self::colored(0)
+    }
+}
+
+impl_collection_builders!(impl<KF, VF, K> HashMapFormat<KF, VF, K>);
+
+impl<KF, VF, K> CollectionFormat for HashMapFormat<KF, VF, K> {
+    fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    fn intersperse_newlines(&self) -> u16 {
+        self.intersperse_newlines
+    }
+
+    fn truncation(&self) -> &Truncation {
+        &self.truncation
+    }
+
+    fn style_overlay(&self) -> &StyleOverlay {
+        &self.style_overlay
+    }
+}
+
 
 #[cfg(test)]
 mod test {
     use crate::{
         Color, ColorDisplay,
-        primitive::U8Format,
+        primitive::{U16Format, U8Format},
         str::StrFormat,
     };
     use super::*;
@@ -75,36 +164,66 @@ mod test {
     #[test]
     fn color_fmt() -> std::fmt::Result {
         let mut map: HashMap<u8, &str> = HashMap::new();
+        map.insert(30, "thirty");
         map.insert(10, "ten");
         map.insert(20, "twenty");
-        map.insert(30, "thirty");
         let mut sink = String::with_capacity(1024);
         map.color_fmt(&mut sink, &HashMapFormat {
             prefix_newlines: 1,
             intersperse_newlines: 1,
             suffix_newlines: 1,
-            key_value_separator: " = ",
+            key_value_separator: " = ".into(),
+            key_value_separator_style_desc: None,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(0),
+            style_overlay: StyleOverlay::INHERIT,
+            key_alignment: None,
+            order: Order::sorted(),
             key_format: U8Format {
-                prefix: "-> ",
+                prefix: "-> ".into(),
                 ..U8Format::colored(1)
             },
             value_format: StrFormat::colored(0),
         })?;
-        // Collect into a Vec<_> to obtain order stability, as the iteration
-        // order is not stable between different HashMap<_> instances. However,
-        // it seems the case that for a given HashMap<_> instance, calling
-        // `set.iter()` repeatedly will always yield the same iteration order.
-        let vec: Vec<(&u8, &&str)> = map.iter().collect();
+        let arrow = Color::White.dimmed().paint("-> ");
         let expected = format!(
-            "\n    -> {} = {}\n    -> {} = {}\n    -> {} = {}\n",
-            Color::Blue.bold().paint(format!("{}", &vec[0].0)),
-            Color::Green.paint(format!("{}", &vec[0].1)),
-            Color::Blue.bold().paint(format!("{}", &vec[1].0)),
-            Color::Green.paint(format!("{}", &vec[1].1)),
-            Color::Blue.bold().paint(format!("{}", &vec[2].0)),
-            Color::Green.paint(format!("{}", &vec[2].1)),
+            "\n    {arrow}{} = {}\n    {arrow}{} = {}\n    {arrow}{} = {}\n",
+            Color::Blue.bold().paint("10"),
+            Color::Green.paint("ten"),
+            Color::Blue.bold().paint("20"),
+            Color::Green.paint("twenty"),
+            Color::Blue.bold().paint("30"),
+            Color::Green.paint("thirty"),
         );
         assert_eq!(sink, expected);
         Ok(())
     }
+
+    #[test]
+    fn ordered_color_fmt() -> std::fmt::Result {
+        let map: HashMap<u16, u8> = [(9, 1), (10, 2), (100, 3)]
+            .into_iter()
+            .collect();
+        // A comparator may capture state, e.g. a key to put first.
+        let first = 10;
+        let pinned = move |a: &u16, b: &u16| {
+            (*a != first).cmp(&(*b != first)).then(a.cmp(b))
+        };
+        for (order, expected) in [
+            (Order::comparator(|a: &u16, b: &u16| b.cmp(a)), "100=3\n10=2\n9=1"),
+            (Order::comparator(pinned), "10=2\n9=1\n100=3"),
+            (Order::sorted(), "9=1\n10=2\n100=3"),
+            // The rendered keys are compared as text.
+            (Order::Rendered, "10=2\n100=3\n9=1"),
+        ] {
+            let mut sink = String::with_capacity(1024);
+            map.color_fmt(&mut sink, &HashMapFormat {
+                key_value_separator: "=".into(),
+                order,
+                ..HashMapFormat::<U16Format, U8Format, _>::monochrome(0)
+            })?;
+            assert_eq!(sink, expected);
+        }
+        Ok(())
+    }
 }
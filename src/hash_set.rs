@@ -1,43 +1,81 @@
-//!
+//! `ColorDisplay` support for `HashSet<T>`.
 
 use super::*;
+use crate::layout::{
+    elide, impl_collection_builders, sort, write_items, CollectionFormat,
+    Layout, Order, Truncation,
+};
 use std::collections::HashSet;
 
-impl<TF, T> ColorDisplay<HashSetFormat<TF>> for HashSet<T>
+impl<TF, T> ColorDisplay<HashSetFormat<TF, T>> for HashSet<T>
 where
     T: ColorDisplay<TF>
 {
     fn color_fmt(
         &self,
         sink: &mut impl Write,
-        format: &HashSetFormat<TF>,
+        format: &HashSetFormat<TF, T>,
     ) -> std::fmt::Result {
-        self.write_newlines(sink, format.prefix_newlines, &format)?;
-        for (idx, item) in self.iter().enumerate() {
-            if idx > 0 {
-                self.write_newlines(sink, format.intersperse_newlines, &format)?;
-            }
-            item.color_fmt(sink, &format.item_format)?;
-        }
-        self.write_newlines(sink, format.suffix_newlines, &format)?;
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &HashSetFormat<TF, T>,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        self.write_newlines_in(sink, format.prefix_newlines, format, ctx)?;
+        let mut items: Vec<_> = self.iter().collect();
+        sort(&mut items, &format.order, &format.item_format, |item| item);
+        write_items(
+            self, sink, format, ctx,
+            elide(items.into_iter(), format.truncation.limit),
+            |sink, ctx, item| item.color_fmt_in(sink, &format.item_format, ctx),
+        )?;
+        self.write_newlines_in(sink, format.suffix_newlines, format, ctx)?;
         Ok(())
     }
 }
 
-#[derive(Clone, Copy)]
-pub struct HashSetFormat<TF> {
+pub struct HashSetFormat<TF, T> {
     pub prefix_newlines: u16,
     pub intersperse_newlines: u16,
     pub suffix_newlines: u16,
+    pub layout: Layout,
+    pub truncation: Truncation,
+    /// Layered over the styles of the descendants, see `StyleOverlay`.
+    pub style_overlay: StyleOverlay,
+    pub order: Order<T>,
     pub item_format: TF,
 }
 
-impl<TF: Format> Format for HashSetFormat<TF> {
+impl<TF: Clone, T> Clone for HashSetFormat<TF, T> {
+    fn clone(&self) -> Self {
+        Self {
+            prefix_newlines: self.prefix_newlines,
+            intersperse_newlines: self.intersperse_newlines,
+            suffix_newlines: self.suffix_newlines,
+            layout: self.layout.clone(),
+            truncation: self.truncation,
+            style_overlay: self.style_overlay,
+            order: self.order.clone(),
+            item_format: self.item_format.clone(),
+        }
+    }
+}
+
+
+impl<TF: Format, T> Format for HashSetFormat<TF, T> {
     fn colored(indent: u16) -> Self {
         Self {
             prefix_newlines: 0,
             intersperse_newlines: 1,
             suffix_newlines: 0,
+            layout: Layout::Lines,
+            truncation: Truncation::colored(indent),
+            style_overlay: StyleOverlay::INHERIT,
+            order: Order::Iteration,
             item_format: TF::colored(indent),
         }
     }
@@ -47,55 +85,104 @@ impl<TF: Format> Format for HashSetFormat<TF> {
             prefix_newlines: 0,
             intersperse_newlines: 1,
             suffix_newlines: 0,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(indent),
+            style_overlay: StyleOverlay::INHERIT,
+            order: Order::Iteration,
             item_format: TF::monochrome(indent),
         }
     }
 }
 
+impl<TF: Format, T> Default for HashSetFormat<TF, T> {
+    fn default() -> Self {
+        Self::colored(0)
+    }
+}
+
+impl_collection_builders!(impl<TF, T> HashSetFormat<TF, T>);
+
+impl<TF, T> CollectionFormat for HashSetFormat<TF, T> {
+    fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    fn intersperse_newlines(&self) -> u16 {
+        self.intersperse_newlines
+    }
+
+    fn truncation(&self) -> &Truncation {
+        &self.truncation
+    }
+
+    fn style_overlay(&self) -> &StyleOverlay {
+        &self.style_overlay
+    }
+}
+
 
 #[cfg(test)]
 mod test {
     use crate::{
         Color, ColorDisplay,
         primitive::U8Format,
+        str::StrFormat,
     };
     use super::*;
 
     #[test]
     fn color_fmt() -> std::fmt::Result {
         let mut set: HashSet<u8> = HashSet::new();
+        set.insert(30);
         set.insert(10);
         set.insert(20);
-        set.insert(30);
         let mut sink = String::with_capacity(1024);
         set.color_fmt(&mut sink, &HashSetFormat {
             prefix_newlines: 1,
             intersperse_newlines: 1,
             suffix_newlines: 1,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(0),
+            style_overlay: StyleOverlay::INHERIT,
+            order: Order::sorted(),
             item_format: U8Format {
-                prefix: "->",
+                prefix: "->".into(),
                 style_desc: Some(StyleDesc {
-                    color: Color::Red,
+                    color: Some(Color::Red),
+                    background: None,
                     bold: true,
                     italic: false,
                     underline: false,
                     dimmed: false,
+                    blink: false,
+                    reverse: false,
+                    hidden: false,
+                    strikethrough: false,
                 }),
                 ..U8Format::colored(1)
             },
         })?;
-        // Collect into a Vec<_> to obtain order stability, as the iteration
-        // order is not stable between different HashSet<_> instances. However,
-        // it seems the case that for a given HashSet<_> instance, calling
-        // `set.iter()` repeatedly will always yield the same iteration order.
-        let vec: Vec<&u8> = set.iter().collect();
+        let arrow = Color::White.dimmed().paint("->");
         let expected = format!(
-            "\n    ->{}\n    ->{}\n    ->{}\n",
-            Color::Red.bold().paint(format!("{}", vec[0])),
-            Color::Red.bold().paint(format!("{}", vec[1])),
-            Color::Red.bold().paint(format!("{}", vec[2])),
+            "\n    {arrow}{}\n    {arrow}{}\n    {arrow}{}\n",
+            Color::Red.bold().paint("10"),
+            Color::Red.bold().paint("20"),
+            Color::Red.bold().paint("30"),
         );
         assert_eq!(sink, expected);
         Ok(())
     }
+
+    #[test]
+    fn borrowed_sorted_color_fmt() -> std::fmt::Result {
+        let words = ["b".to_string(), "c".to_string(), "a".to_string()];
+        let set: HashSet<&str> = words.iter().map(String::as_str).collect();
+        let mut sink = String::with_capacity(1024);
+        set.color_fmt(&mut sink, &HashSetFormat {
+            order: Order::sorted(),
+            ..HashSetFormat::<StrFormat, _>::monochrome(0).inline()
+        })?;
+        assert_eq!(sink, "a, b, c");
+        Ok(())
+    }
 }
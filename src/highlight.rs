@@ -0,0 +1,188 @@
+//! Rules to highlight parts of text, e.g. search hits, with a style that is
+//! layered over the style that the text is written with.
+
+use crate::StyleDesc;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// A list of highlight rules: a static slice, or a `Vec` built at runtime,
+/// e.g. `vec![rule].into()`.  Cloning a static list copies no rules.
+pub type Highlights = Cow<'static, [Highlight]>;
+
+/// A rule that highlights all (non-overlapping) matches of `pattern`.
+#[derive(Clone, Debug)]
+pub struct Highlight {
+    pub pattern: Pattern,
+    /// Layered over the style of the highlighted text, see
+    /// `StyleDesc::layered()`.
+    pub style_desc: StyleDesc,
+}
+
+impl Highlight {
+    pub fn literal(
+        text: impl Into<Cow<'static, str>>,
+        style_desc: StyleDesc,
+    ) -> Self {
+        Self { pattern: Pattern::Literal(text.into()), style_desc }
+    }
+
+    pub fn case_insensitive(
+        text: impl Into<Cow<'static, str>>,
+        style_desc: StyleDesc,
+    ) -> Self {
+        Self { pattern: Pattern::CaseInsensitive(text.into()), style_desc }
+    }
+
+    #[cfg(feature = "regex")]
+    pub fn regex(regex: regex::Regex, style_desc: StyleDesc) -> Self {
+        Self { pattern: Pattern::Regex(regex), style_desc }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    /// Matches the text exactly.
+    Literal(Cow<'static, str>),
+    /// Matches the text regardless of case, using Unicode lowercasing.
+    CaseInsensitive(Cow<'static, str>),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl Pattern {
+    /// The byte ranges of the leftmost, non-overlapping, non-empty matches
+    /// of `self` in `text`.
+    pub fn find_iter(&self, text: &str) -> Vec<Range<usize>> {
+        match self {
+            Self::Literal(needle) if needle.is_empty() => vec![],
+            Self::Literal(needle) => text.match_indices(needle.as_ref())
+                .map(|(idx, m)| idx..idx + m.len())
+                .collect(),
+            Self::CaseInsensitive(needle) if needle.is_empty() => vec![],
+            Self::CaseInsensitive(needle) => {
+                let mut matches = vec![];
+                let mut idx = 0;
+                while idx < text.len() {
+                    match match_len_ignoring_case(&text[idx..], needle) {
+                        Some(len) => {
+                            matches.push(idx..idx + len);
+                            idx += len;
+                        },
+                        None => {
+                            let c = text[idx..].chars().next().unwrap_or_default();
+                            idx += c.len_utf8();
+                        },
+                    }
+                }
+                matches
+            },
+            #[cfg(feature = "regex")]
+            Self::Regex(regex) => regex.find_iter(text)
+                .filter(|m| !m.range().is_empty())
+                .map(|m| m.range())
+                .collect(),
+        }
+    }
+}
+
+/// The length in bytes of the prefix of `haystack` that equals `needle`
+/// when both are lowercased, if any.
+fn match_len_ignoring_case(haystack: &str, needle: &str) -> Option<usize> {
+    let mut chars = haystack.char_indices();
+    for n in needle.chars() {
+        let (_, h) = chars.next()?;
+        if !h.to_lowercase().eq(n.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.next().map_or(haystack.len(), |(idx, _)| idx))
+}
+
+/// For each byte of `text`, the index of the rule in `highlights` that
+/// highlights it, if any.  Overlapping matches are resolved in favor of the
+/// rule that comes first in `highlights`: a later rule only highlights the
+/// part of a match that no earlier rule highlights.
+pub(crate) fn owners(text: &str, highlights: &[Highlight]) -> Vec<Option<usize>> {
+    let mut owners = vec![None; text.len()];
+    for (rule, highlight) in highlights.iter().enumerate() {
+        for range in highlight.pattern.find_iter(text) {
+            for owner in &mut owners[range] {
+                owner.get_or_insert(rule);
+            }
+        }
+    }
+    owners
+}
+
+/// The style of text that is written with style `base`, and highlighted by
+/// `highlight`, if any.
+pub(crate) fn layered(
+    base: Option<StyleDesc>,
+    highlight: Option<&Highlight>,
+) -> Option<StyleDesc> {
+    match (base, highlight) {
+        (base, None) => base,
+        (None, Some(highlight)) => Some(highlight.style_desc),
+        (Some(base), Some(highlight)) => Some(base.layered(highlight.style_desc)),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::Color;
+    use super::*;
+
+    const STYLE: StyleDesc = StyleDesc {
+        color: Some(Color::Red),
+        background: None,
+        bold: false,
+        italic: false,
+        underline: false,
+        dimmed: false,
+        blink: false,
+        reverse: false,
+        hidden: false,
+        strikethrough: false,
+    };
+
+    #[test]
+    fn overlapping_owners() {
+        let highlights = [
+            Highlight::literal("bc", STYLE),
+            Highlight::case_insensitive("ABCD", STYLE),
+            Highlight::case_insensitive("", STYLE),
+        ];
+        let owners = owners("xabcd abCD", &highlights);
+        assert_eq!(owners, [
+            None, Some(1), Some(0), Some(0), Some(1),
+            None, Some(1), Some(1), Some(1), Some(1),
+        ]);
+    }
+
+    #[test]
+    fn case_insensitive_find_iter() {
+        let pattern = Pattern::CaseInsensitive("straße".into());
+        let text = "STRASSE Straße sTRAßE";
+        assert_eq!(pattern.find_iter(text), [8..15, 16..23]);
+    }
+
+    #[test]
+    fn background_only_layered() {
+        let base = StyleDesc { strikethrough: true, ..STYLE };
+        let hit = Highlight::literal("a", StyleDesc {
+            background: Some(Color::Yellow),
+            reverse: true,
+            ..StyleDesc::PLAIN
+        });
+        let style = crate::compute_leaf_style(layered(Some(base), Some(&hit)));
+        assert_eq!(style, Color::Red.on(Color::Yellow).reverse().strikethrough());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex_find_iter() {
+        let pattern = Pattern::Regex(regex::Regex::new(r"\d+|x*").unwrap());
+        assert_eq!(pattern.find_iter("a12b3"), [1..3, 4..5]);
+    }
+}
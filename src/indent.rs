@@ -0,0 +1,158 @@
+//! A `Write` wrapper that keeps multi-line output aligned.
+
+use crate::ansi::{is_reset, RESET};
+use std::fmt::Write;
+
+/// Writes to an inner sink, and re-applies an indentation after every
+/// newline that is written through it.
+///
+/// Styles don't leak into the indentation: any style that is active at
+/// the end of a line is reset before the newline, and re-opened after the
+/// indentation of the next line.  The indentation is written lazily, i.e.
+/// only once the next line contains something other than newlines, so that
+/// no trailing whitespace is produced.
+///
+/// Optionally, newlines can be translated to another newline token, e.g.
+/// `"\r\n"`.  Newlines that already are part of a `"\r\n"` are left as-is.
+///
+/// An escape sequence may be split across writes, so call `finish()` once
+/// done, to write an escape sequence that was left unterminated as-is.
+pub struct IndentWriter<'s, W: Write> {
+    sink: &'s mut W,
+    indentation: String,
+    newline: Option<String>,
+    /// Whether the last character written was a `'\r'`.
+    carriage_return: bool,
+    /// Whether a newline was written, but the indentation wasn't yet.
+    pending: bool,
+    /// The SGR sequences written since the last reset.
+    active: Vec<String>,
+    /// A partially written escape sequence.
+    escape: Option<String>,
+}
+
+impl<'s, W: Write> IndentWriter<'s, W> {
+    pub fn new(sink: &'s mut W, indentation: impl Into<String>) -> Self {
+        Self {
+            sink,
+            indentation: indentation.into(),
+            newline: None,
+            carriage_return: false,
+            pending: false,
+            active: vec![],
+            escape: None,
+        }
+    }
+
+    /// Translate every `'\n'` written through `self` to `newline`.
+    pub fn with_newline(self, newline: impl Into<String>) -> Self {
+        let newline = Some(newline.into()).filter(|newline| newline != "\n");
+        Self { newline, ..self }
+    }
+
+    /// Write the escape sequence that is still pending, if any, as-is.
+    pub fn finish(mut self) -> std::fmt::Result {
+        match self.escape.take() {
+            Some(escape) => self.sink.write_str(&escape),
+            None => Ok(()),
+        }
+    }
+
+    fn finish_escape(&mut self, escape: String, out: &mut String) {
+        if escape.ends_with('m') {
+            if is_reset(&escape) {
+                self.active.clear();
+            } else {
+                self.active.push(escape.clone());
+            }
+            if self.pending {
+                // Re-opened after the indentation, if still active by then.
+                return;
+            }
+        }
+        out.push_str(&escape);
+    }
+}
+
+impl<W: Write> Write for IndentWriter<'_, W> {
+    fn write_str(&mut self, text: &str) -> std::fmt::Result {
+        let mut out = String::with_capacity(text.len());
+        for c in text.chars() {
+            let carriage_return =
+                std::mem::replace(&mut self.carriage_return, c == '\r');
+            if let Some(mut escape) = self.escape.take() {
+                escape.push(c);
+                let is_csi = escape.starts_with("\x1b[");
+                let complete = if is_csi {
+                    escape.len() > 2 && ('@'..='~').contains(&c)
+                } else {
+                    escape.chars().count() == 2 && c != '['
+                };
+                if complete {
+                    self.finish_escape(escape, &mut out);
+                } else {
+                    self.escape = Some(escape);
+                }
+                continue;
+            }
+            match c {
+                '\x1b' => self.escape = Some(c.to_string()),
+                '\n' => {
+                    if !self.pending && !self.active.is_empty() {
+                        out.push_str(RESET);
+                    }
+                    match &self.newline {
+                        Some(newline) if !carriage_return => {
+                            out.push_str(newline);
+                        },
+                        _ => out.push('\n'),
+                    }
+                    self.pending = true;
+                },
+                '\r' => out.push('\r'),
+                c => {
+                    if self.pending {
+                        self.pending = false;
+                        out.push_str(&self.indentation);
+                        for style in &self.active {
+                            out.push_str(style);
+                        }
+                    }
+                    out.push(c);
+                },
+            }
+        }
+        self.sink.write_str(&out)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::Color;
+    use super::*;
+
+    #[test]
+    fn reindents_and_reopens_styles() -> std::fmt::Result {
+        let mut sink = String::with_capacity(1024);
+        let mut writer = IndentWriter::new(&mut sink, "  ");
+        write!(writer, "{}", Color::Red.bold().paint("ab\n\ncd\n"))?;
+        write!(writer, "ef")?;
+        let red = Color::Red.bold().prefix().to_string();
+        let expected = format!("{red}ab\x1b[0m\n\n  {red}cd\x1b[0m\n  ef");
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn stray_escapes() -> std::fmt::Result {
+        for text in ["user\x1bé input continues", "ends with esc\x1b", "x\x1b[12"] {
+            let mut sink = String::with_capacity(1024);
+            let mut writer = IndentWriter::new(&mut sink, "  ");
+            writer.write_str(text)?;
+            writer.finish()?;
+            assert_eq!(sink, text);
+        }
+        Ok(())
+    }
+}
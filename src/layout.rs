@@ -0,0 +1,697 @@
+//! The layouts with which collections arrange their items,
+//! and the ways in which they truncate them.
+
+use crate::{ansi, Color, ColorDisplay, Context, StyleDesc, StyleOverlay, Text};
+use crate::table::Alignment;
+use std::cmp;
+use std::fmt::Write;
+use std::rc::Rc;
+
+#[derive(Clone)]
+pub enum Layout {
+    /// Items are separated by a configurable number of newlines.
+    Lines,
+    /// Every item is written on its own line, preceded by guides like `├── `
+    /// that visualize the nesting of collections.
+    Tree(TreeGuides),
+    /// Items are written on the current line, separated by a separator
+    /// like `", "`.
+    Inline(Text),
+}
+
+/// Describes how to draw the guides of a `Layout::Tree`.
+#[derive(Clone, Copy)]
+pub struct TreeGuides {
+    pub charset: TreeCharset,
+    /// The styles of the guides, indexed by nesting depth.
+    /// The styles are cycled through when there are more levels than styles.
+    pub depth_styles: &'static [StyleDesc],
+}
+
+impl TreeGuides {
+    pub const fn colored(charset: TreeCharset) -> Self {
+        Self { charset, depth_styles: COLORED_DEPTH_STYLES }
+    }
+
+    pub const fn monochrome(charset: TreeCharset) -> Self {
+        Self { charset, depth_styles: &[] }
+    }
+
+    fn style(&self, depth: u16) -> Option<StyleDesc> {
+        match self.depth_styles.len() {
+            0 => None,
+            len => Some(self.depth_styles[depth as usize % len]),
+        }
+    }
+
+    /// The guide that precedes an item.
+    fn branch(&self, depth: u16, last: bool, ctx: &Context) -> String {
+        let (branch, last_branch, _, _) = self.charset.tokens();
+        let style = ctx.leaf_style(self.style(depth));
+        let branch = if last { last_branch } else { branch };
+        style.paint(branch).to_string()
+    }
+
+    /// The guide that precedes the lines of the descendants of an item.
+    fn continuation(&self, depth: u16, last: bool, ctx: &Context) -> String {
+        let (_, _, continuation, blank) = self.charset.tokens();
+        let style = ctx.leaf_style(self.style(depth));
+        if last { blank.to_string() } else { style.paint(continuation).to_string() }
+    }
+}
+
+const COLORED_DEPTH_STYLES: &[StyleDesc] = &[
+    StyleDesc::fg(Color::Blue).dimmed(),
+    StyleDesc::fg(Color::Cyan).dimmed(),
+    StyleDesc::fg(Color::Purple).dimmed(),
+    StyleDesc::fg(Color::Yellow).dimmed(),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TreeCharset {
+    /// Box-drawing characters, e.g. `├── `, `│   ` and `└── `.
+    Unicode,
+    /// An ASCII fallback, e.g. `|-- `, `|   ` and `` `-- ``.
+    Ascii,
+}
+
+impl TreeCharset {
+    /// The branch, last branch, continuation and blank tokens.
+    fn tokens(self) -> (&'static str, &'static str, &'static str, &'static str) {
+        match self {
+            Self::Unicode => ("├── ", "└── ", "│   ", "    "),
+            Self::Ascii => ("|-- ", "`-- ", "|   ", "    "),
+        }
+    }
+}
+
+/// Describes how a collection limits the number of items it writes.
+#[derive(Clone, Copy)]
+pub struct Truncation {
+    /// The items to write, or `None` to write all of them.
+    pub limit: Option<Limit>,
+    /// The indentation of the elision marker that replaces omitted items.
+    pub indent: u16,
+    /// The style of the elision marker, as well as of the placeholder that
+    /// replaces the items of a collection nested deeper than
+    /// `Context::max_depth`.
+    pub style_desc: Option<StyleDesc>,
+}
+
+impl Truncation {
+    pub const fn colored(indent: u16) -> Self {
+        Self {
+            limit: None,
+            indent,
+            style_desc: Some(StyleDesc::fg(Color::White).dimmed().italic()),
+        }
+    }
+
+    pub const fn monochrome(indent: u16) -> Self {
+        Self { limit: None, indent, style_desc: None }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    /// Write only the first `n` items.
+    Head(usize),
+    /// Write only the first `head` and the last `tail` items.
+    HeadTail { head: usize, tail: usize },
+}
+
+/// The order in which a collection without a meaningful order of its own,
+/// e.g. a `HashSet<T>`, writes its items.  For maps, the entries are ordered
+/// by their keys.
+pub enum Order<T> {
+    /// The iteration order of the collection, which differs between runs.
+    Iteration,
+    /// Sorted by a comparator, which may capture state, e.g. a collation.
+    Comparator(Rc<Comparator<T>>),
+    /// Sorted by a comparator without state.  Unlike a `Comparator`, it
+    /// works for items that borrow data, e.g. `&str`.
+    Function(fn(&T, &T) -> cmp::Ordering),
+    /// Sorted by the monochrome text that the items are rendered to with
+    /// the format of the collection, so that the order matches the output.
+    Rendered,
+}
+
+type Comparator<T> = dyn Fn(&T, &T) -> cmp::Ordering;
+
+impl<T> Order<T> {
+    /// Sorted by `cmp`, see `Order::Comparator`.
+    pub fn comparator(cmp: impl Fn(&T, &T) -> cmp::Ordering + 'static) -> Self {
+        Self::Comparator(Rc::new(cmp))
+    }
+}
+
+impl<T: Ord> Order<T> {
+    /// Sorted by the `Ord` impl of `T`.
+    pub fn sorted() -> Self {
+        Self::Function(T::cmp)
+    }
+}
+
+impl<T> Clone for Order<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Iteration => Self::Iteration,
+            Self::Comparator(cmp) => Self::Comparator(cmp.clone()),
+            Self::Function(cmp) => Self::Function(*cmp),
+            Self::Rendered => Self::Rendered,
+        }
+    }
+}
+
+/// Sort `items` according to `order`, where `key` yields the part of an item
+/// that is compared, and that is rendered with `key_format` if need be.
+pub(crate) fn sort<I, K, KF>(
+    items: &mut [I],
+    order: &Order<K>,
+    key_format: &KF,
+    key: impl Fn(&I) -> &K,
+) where
+    K: ColorDisplay<KF>,
+{
+    match order {
+        Order::Iteration => {},
+        Order::Comparator(cmp) => items.sort_by(|a, b| cmp(key(a), key(b))),
+        Order::Function(cmp) => items.sort_by(|a, b| cmp(key(a), key(b))),
+        Order::Rendered => items.sort_by_cached_key(|item| {
+            let mut rendered = String::new();
+            let _ = key(item).color_fmt(&mut rendered, key_format);
+            ansi::strip_escapes(&rendered)
+        }),
+    }
+}
+
+/// Describes how the keys of a map are padded, so that the key-value
+/// separators and the values that follow them line up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyAlignment {
+    pub alignment: Alignment,
+    /// Keys that are wider than this are written without padding, and don't
+    /// affect the width that the other keys are padded to.
+    pub max_width: usize,
+}
+
+impl KeyAlignment {
+    pub const fn left(max_width: usize) -> Self {
+        Self { alignment: Alignment::Left, max_width }
+    }
+
+    pub const fn right(max_width: usize) -> Self {
+        Self { alignment: Alignment::Right, max_width }
+    }
+
+    /// The width that `keys` are padded to when rendered with `key_format`.
+    pub(crate) fn width<'k, K, KF>(
+        &self,
+        keys: impl IntoIterator<Item = &'k K>,
+        key_format: &KF,
+    ) -> Result<usize, std::fmt::Error>
+    where
+        K: ColorDisplay<KF> + 'k,
+    {
+        let mut width = 0;
+        for key in keys {
+            let mut rendered = String::new();
+            key.color_fmt(&mut rendered, key_format)?;
+            let key_width = key_width(&rendered);
+            if key_width <= self.max_width {
+                width = width.max(key_width);
+            }
+        }
+        Ok(width)
+    }
+
+    /// Pad `rendered`, a rendered key, to `width`.  The indentation of the key
+    /// is left as-is.
+    pub(crate) fn pad(&self, rendered: &str, width: usize) -> String {
+        let key_width = key_width(rendered);
+        if key_width > self.max_width {
+            return rendered.to_string();
+        }
+        let indentation = rendered.len() - rendered.trim_start_matches([' ', '\t']).len();
+        let (indentation, key) = rendered.split_at(indentation);
+        let padding = width.saturating_sub(key_width);
+        let (left, right) = match self.alignment {
+            Alignment::Left => (0, padding),
+            Alignment::Right => (padding, 0),
+            Alignment::Center => (padding / 2, padding - padding / 2),
+        };
+        format!("{indentation}{}{key}{}", " ".repeat(left), " ".repeat(right))
+    }
+}
+
+/// The visible width of the last line of a rendered key, excluding its
+/// indentation.
+fn key_width(rendered: &str) -> usize {
+    let stripped = ansi::strip_escapes(rendered);
+    let last_line = stripped.rsplit('\n').next().unwrap_or_default();
+    ansi::visible_width(last_line.trim_start_matches([' ', '\t']))
+}
+
+/// Write `key` to `sink` with `key_format`, padded to `width` if aligned.
+pub(crate) fn write_key<K, KF, W>(
+    sink: &mut W,
+    ctx: &mut Context,
+    key: &K,
+    key_format: &KF,
+    alignment: Option<(&KeyAlignment, usize)>,
+) -> std::fmt::Result
+where
+    K: ColorDisplay<KF> + ?Sized,
+    W: Write,
+{
+    let Some((alignment, width)) = alignment else {
+        return key.color_fmt_in(sink, key_format, ctx);
+    };
+    let mut rendered = String::new();
+    let column = ctx.column;
+    key.color_fmt_in(&mut rendered, key_format, ctx)?;
+    ctx.column = column;
+    ctx.write(sink, &alignment.pad(&rendered, width))
+}
+
+/// The text that replaces the items of a collection that is nested
+/// deeper than `Context::max_depth`.
+pub const DEPTH_PLACEHOLDER: &str = "…";
+
+/// The properties shared by the formats of all collections.
+pub(crate) trait CollectionFormat {
+    fn layout(&self) -> &Layout;
+    fn intersperse_newlines(&self) -> u16;
+    fn truncation(&self) -> &Truncation;
+    fn style_overlay(&self) -> &StyleOverlay;
+}
+
+/// Implement the builder methods shared by the formats of all collections.
+macro_rules! impl_collection_builders {
+    (impl<$($param:ident),*> $format:ty) => {
+        impl<$($param),*> $format {
+            /// Write the items on the current line, separated by `", "`.
+            pub fn inline(self) -> Self {
+                Self {
+                    prefix_newlines: 0,
+                    suffix_newlines: 0,
+                    layout: $crate::layout::Layout::Inline(", ".into()),
+                    ..self
+                }
+            }
+
+            /// Write the items on lines of their own.
+            pub fn lines(self) -> Self {
+                Self {
+                    intersperse_newlines: self.intersperse_newlines.max(1),
+                    layout: $crate::layout::Layout::Lines,
+                    ..self
+                }
+            }
+
+            /// Write the items as a tree, with `guides`.
+            pub fn tree(self, guides: $crate::layout::TreeGuides) -> Self {
+                Self { layout: $crate::layout::Layout::Tree(guides), ..self }
+            }
+
+            /// Write only the first `max_items` items.
+            pub fn max_items(self, max_items: usize) -> Self {
+                self.limit($crate::layout::Limit::Head(max_items))
+            }
+
+            /// Write only the first `head` and the last `tail` items.
+            pub fn head_tail(self, head: usize, tail: usize) -> Self {
+                self.limit($crate::layout::Limit::HeadTail { head, tail })
+            }
+
+            fn limit(self, limit: $crate::layout::Limit) -> Self {
+                let truncation = $crate::layout::Truncation {
+                    limit: Some(limit),
+                    ..self.truncation
+                };
+                Self { truncation, ..self }
+            }
+
+            pub fn with_style_overlay(self, style_overlay: $crate::StyleOverlay) -> Self {
+                Self { style_overlay, ..self }
+            }
+        }
+    };
+}
+
+pub(crate) use impl_collection_builders;
+
+/// Either an item of a collection, or the number of items omitted from it.
+pub(crate) enum Entry<T> {
+    Item(T),
+    Elided(usize),
+}
+
+/// Apply `limit` to `items`, replacing the omitted items with an elision.
+pub(crate) fn elide<I: ExactSizeIterator>(
+    items: I,
+    limit: Option<Limit>,
+) -> impl Iterator<Item = Entry<I::Item>> {
+    let len = items.len();
+    let (head, tail) = match limit {
+        None => (len, 0),
+        Some(Limit::Head(head)) => (head, 0),
+        Some(Limit::HeadTail { head, tail }) => (head, tail),
+    };
+    let elided = len.saturating_sub(head.saturating_add(tail));
+    items.enumerate().filter_map(move |(idx, item)| match idx {
+        _ if elided == 0 || idx < head || idx >= head + elided => {
+            Some(Entry::Item(item))
+        },
+        _ if idx == head => Some(Entry::Elided(elided)),
+        _ => None,
+    })
+}
+
+/// Write `entries` to `sink` according to the layout of `format`, using
+/// `write_item` to write each individual item.  The depth of `ctx` is
+/// incremented while an entry is written, and the style overlay of `format`
+/// is added to that of `ctx` while the collection is written.  When there are
+/// entries and the maximum depth of `ctx` has been reached, a placeholder is
+/// written instead.
+pub(crate) fn write_items<C, F, W, T>(
+    container: &C,
+    sink: &mut W,
+    format: &F,
+    ctx: &mut Context,
+    entries: impl IntoIterator<Item = Entry<T>>,
+    write_item: impl FnMut(&mut W, &mut Context, T) -> std::fmt::Result,
+) -> std::fmt::Result
+where
+    C: ColorDisplay<F> + ?Sized,
+    F: CollectionFormat,
+    W: Write,
+{
+    let outer = ctx.style_overlay;
+    ctx.style_overlay = format.style_overlay().or(outer);
+    let written = write_entries(container, sink, format, ctx, entries, write_item);
+    ctx.style_overlay = outer;
+    written
+}
+
+fn write_entries<C, F, W, T>(
+    container: &C,
+    sink: &mut W,
+    format: &F,
+    ctx: &mut Context,
+    entries: impl IntoIterator<Item = Entry<T>>,
+    mut write_item: impl FnMut(&mut W, &mut Context, T) -> std::fmt::Result,
+) -> std::fmt::Result
+where
+    C: ColorDisplay<F> + ?Sized,
+    F: CollectionFormat,
+    W: Write,
+{
+    let truncation = format.truncation();
+    let style = ctx.leaf_style(truncation.style_desc);
+    let mut entries = entries.into_iter().peekable();
+    if entries.peek().is_some() && ctx.max_depth_reached() {
+        return ctx.write(sink, &style.paint(DEPTH_PLACEHOLDER).to_string());
+    }
+    let mut write_entry = |sink: &mut W, ctx: &mut Context, entry| match entry {
+        Entry::Item(item) => write_item(sink, ctx, item),
+        Entry::Elided(count) => {
+            let indent = ctx.indentation(truncation.indent);
+            container.write_indentation_in(sink, indent, format, ctx)?;
+            let marker = style.paint(format!("… {count} more"));
+            ctx.write(sink, &marker.to_string())
+        },
+    };
+    let mut idx = 0;
+    while let Some(entry) = entries.next() {
+        match format.layout() {
+            Layout::Lines => {
+                if idx > 0 {
+                    let newlines = format.intersperse_newlines();
+                    container.write_newlines_in(sink, newlines, format, ctx)?;
+                }
+                ctx.depth += 1;
+                write_entry(sink, ctx, entry)?;
+                ctx.depth -= 1;
+            },
+            Layout::Tree(guides) => {
+                // A nested tree starts on a new line, below its parent item.
+                if idx > 0 || ctx.depth > 0 {
+                    container.write_newlines_in(sink, 1, format, ctx)?;
+                }
+                let mut prefix = ctx.guides.concat();
+                let last = entries.peek().is_none();
+                prefix.push_str(&guides.branch(ctx.depth, last, ctx));
+                ctx.write(sink, &prefix)?;
+                let continuation = guides.continuation(ctx.depth, last, ctx);
+                ctx.guides.push(continuation);
+                ctx.depth += 1;
+                write_entry(sink, ctx, entry)?;
+                ctx.depth -= 1;
+                ctx.guides.pop();
+            },
+            Layout::Inline(separator) => {
+                if idx > 0 {
+                    ctx.write(sink, separator)?;
+                }
+                ctx.depth += 1;
+                write_entry(sink, ctx, entry)?;
+                ctx.depth -= 1;
+            },
+        }
+        idx += 1;
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        btree_map::BTreeMapFormat,
+        primitive::U8Format,
+        slice::SliceFormat,
+        str::StrFormat,
+        vec::VecFormat,
+        Cascade, Color, ColorDisplay, Context, Format,
+    };
+    use std::collections::BTreeMap;
+    use super::*;
+
+    #[test]
+    fn tree_color_fmt() -> std::fmt::Result {
+        let mut map: BTreeMap<&str, Vec<u8>> = BTreeMap::new();
+        map.insert("a", vec![1, 2]);
+        map.insert("b", vec![3]);
+        let mut sink = String::with_capacity(1024);
+        map.color_fmt(&mut sink, &BTreeMapFormat {
+            prefix_newlines: 0,
+            intersperse_newlines: 1,
+            suffix_newlines: 0,
+            key_value_separator: ":".into(),
+            key_value_separator_style_desc: None,
+            layout: Layout::Tree(TreeGuides::monochrome(TreeCharset::Unicode)),
+            truncation: Truncation::monochrome(0),
+            style_overlay: StyleOverlay::INHERIT,
+            key_alignment: None,
+            key_format: StrFormat::monochrome(0),
+            value_format: VecFormat {
+                prefix_newlines: 0,
+                intersperse_newlines: 1,
+                suffix_newlines: 0,
+                layout: Layout::Tree(TreeGuides::monochrome(TreeCharset::Unicode)),
+                truncation: Truncation::monochrome(0),
+                style_overlay: StyleOverlay::INHERIT,
+                item_format: U8Format::monochrome(0),
+            },
+        })?;
+        let expected = concat!(
+            "├── a:\n",
+            "│   ├── 1\n",
+            "│   └── 2\n",
+            "└── b:\n",
+            "    └── 3",
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn ascii_tree_color_fmt() -> std::fmt::Result {
+        let nested: &[Vec<u8>] = &[vec![1], vec![2]];
+        let mut sink = String::with_capacity(1024);
+        let guides = TreeGuides::colored(TreeCharset::Ascii);
+        nested.color_fmt(&mut sink, &SliceFormat {
+            prefix_newlines: 0,
+            intersperse_newlines: 1,
+            suffix_newlines: 0,
+            layout: Layout::Tree(guides),
+            truncation: Truncation::monochrome(0),
+            style_overlay: StyleOverlay::INHERIT,
+            item_format: VecFormat {
+                prefix_newlines: 0,
+                intersperse_newlines: 1,
+                suffix_newlines: 0,
+                layout: Layout::Tree(guides),
+                truncation: Truncation::monochrome(0),
+                style_overlay: StyleOverlay::INHERIT,
+                item_format: U8Format::monochrome(0),
+            },
+        })?;
+        let depth0 = Color::Blue.dimmed();
+        let depth1 = Color::Cyan.dimmed();
+        let expected = format!(
+            "{}\n{}{}1\n{}\n    {}2",
+            depth0.paint("|-- "),
+            depth0.paint("|   "),
+            depth1.paint("`-- "),
+            depth0.paint("`-- "),
+            depth1.paint("`-- "),
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn multi_line_tree_color_fmt() -> std::fmt::Result {
+        let nested: &[Vec<&str>] = &[vec!["a\nb", "c"], vec!["d"]];
+        let mut sink = String::with_capacity(1024);
+        let guides = TreeGuides::monochrome(TreeCharset::Unicode);
+        nested.color_fmt(&mut sink, &SliceFormat {
+            item_format: VecFormat {
+                item_format: StrFormat::monochrome(0),
+                ..VecFormat::monochrome(0)
+            }.tree(guides),
+            ..SliceFormat::monochrome(0)
+        }.tree(guides))?;
+        let expected = concat!(
+            "├── \n",
+            "│   ├── a\n",
+            "│   │   b\n",
+            "│   └── c\n",
+            "└── \n",
+            "    └── d",
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_color_fmt() -> std::fmt::Result {
+        let slice: &[u8] = &[1, 2, 3, 4, 5, 6];
+        let mut sink = String::with_capacity(1024);
+        slice.color_fmt(&mut sink, &SliceFormat {
+            truncation: Truncation {
+                limit: Some(Limit::HeadTail { head: 2, tail: 1 }),
+                ..Truncation::colored(1)
+            },
+            ..SliceFormat::<U8Format>::monochrome(1)
+        })?;
+        let marker = Color::White.italic().dimmed().paint("… 3 more");
+        assert_eq!(sink, format!("    1\n    2\n    {marker}\n    6"));
+
+        let mut sink = String::with_capacity(1024);
+        slice.color_fmt(&mut sink, &SliceFormat {
+            layout: Layout::Tree(TreeGuides::monochrome(TreeCharset::Ascii)),
+            truncation: Truncation {
+                limit: Some(Limit::Head(1)),
+                ..Truncation::monochrome(0)
+            },
+            ..SliceFormat::<U8Format>::monochrome(0)
+        })?;
+        assert_eq!(sink, "|-- 1\n`-- … 5 more");
+
+        // Limits that cover the whole collection don't elide anything.
+        let mut sink = String::with_capacity(1024);
+        slice.color_fmt(&mut sink, &SliceFormat {
+            intersperse_newlines: 0,
+            truncation: Truncation {
+                limit: Some(Limit::HeadTail { head: 3, tail: 3 }),
+                ..Truncation::monochrome(0)
+            },
+            ..SliceFormat::<U8Format>::monochrome(0)
+        })?;
+        assert_eq!(sink, "123456");
+        Ok(())
+    }
+
+    #[test]
+    fn max_depth_color_fmt() -> std::fmt::Result {
+        let mut map: BTreeMap<&str, Vec<u8>> = BTreeMap::new();
+        map.insert("a", vec![1, 2]);
+        map.insert("b", vec![]);
+        let mut sink = String::with_capacity(1024);
+        let mut ctx = Context { max_depth: Some(1), ..Context::default() };
+        map.color_fmt_in(&mut sink, &BTreeMapFormat {
+            prefix_newlines: 0,
+            intersperse_newlines: 1,
+            suffix_newlines: 0,
+            key_value_separator: ": ".into(),
+            key_value_separator_style_desc: None,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(0),
+            style_overlay: StyleOverlay::INHERIT,
+            key_alignment: None,
+            key_format: StrFormat::monochrome(0),
+            value_format: VecFormat {
+                prefix_newlines: 1,
+                intersperse_newlines: 1,
+                suffix_newlines: 0,
+                layout: Layout::Lines,
+                truncation: Truncation::colored(0),
+                style_overlay: StyleOverlay::INHERIT,
+                item_format: U8Format::monochrome(0),
+            },
+        }, &mut ctx)?;
+        // Empty collections need no placeholder.
+        let placeholder = Color::White.italic().dimmed().paint("…");
+        assert_eq!(sink, format!("a: \n{placeholder}\nb: \n"));
+        Ok(())
+    }
+
+    #[test]
+    fn style_overlay_color_fmt() -> std::fmt::Result {
+        let nested: &[Vec<u8>] = &[vec![1], vec![2]];
+        let mut sink = String::with_capacity(1024);
+        let stale = StyleOverlay {
+            color: Cascade::Set(Color::White),
+            dimmed: Cascade::Set(true),
+            ..StyleOverlay::INHERIT
+        };
+        nested.color_fmt(&mut sink, &SliceFormat {
+            prefix_newlines: 0,
+            intersperse_newlines: 0,
+            suffix_newlines: 0,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(0),
+            style_overlay: stale,
+            item_format: VecFormat {
+                prefix_newlines: 0,
+                intersperse_newlines: 0,
+                suffix_newlines: 0,
+                layout: Layout::Lines,
+                truncation: Truncation::colored(0),
+                style_overlay: StyleOverlay {
+                    bold: Cascade::Clear,
+                    dimmed: Cascade::Clear,
+                    ..StyleOverlay::INHERIT
+                },
+                item_format: U8Format::colored(0),
+            },
+        })?;
+        // Inner overlays take precedence over outer ones, per attribute.
+        let item = |text: &'static str| Color::White.paint(text).to_string();
+        assert_eq!(sink, format!("    {}{}", item("1"), item("2")));
+
+        // Monochrome items below an overlay get its style.
+        let mut sink = String::with_capacity(1024);
+        [1u8, 2][..].color_fmt(&mut sink, &SliceFormat {
+            intersperse_newlines: 0,
+            style_overlay: stale,
+            ..SliceFormat::<U8Format>::monochrome(0)
+        })?;
+        let item = |text: &'static str| Color::White.dimmed().paint(text).to_string();
+        assert_eq!(sink, format!("{}{}", item("1"), item("2")));
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+//! The rendering shared by the formats of leaf values, i.e. of values that
+//! aren't collections: indentation, a prefix, the body between delimiters,
+//! and a suffix, each piece of punctuation with a style of its own.
+
+use crate::{ColorDisplay, Context, StyleDesc};
+use std::fmt::Write;
+
+/// A piece of punctuation around the body of a leaf value, and its style.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Affix<'f> {
+    pub text: &'f str,
+    pub style_desc: Option<StyleDesc>,
+}
+
+/// The properties shared by the formats of all leaf values.
+pub trait LeafFormat {
+    fn indent(&self) -> u16;
+    fn prefix(&self) -> Affix<'_>;
+    /// Written both before and after the body.
+    fn delimiter(&self) -> Affix<'_>;
+    fn suffix(&self) -> Affix<'_>;
+}
+
+/// Implement `LeafFormat` for formats with the fields `indent`, `prefix`,
+/// `delimiter` and `suffix`, each of the latter with a `_style_desc`.
+macro_rules! impl_leaf_format {
+    ($format:ty) => {
+        impl $crate::leaf::LeafFormat for $format {
+            fn indent(&self) -> u16 {
+                self.indent
+            }
+
+            fn prefix(&self) -> $crate::leaf::Affix<'_> {
+                $crate::leaf::Affix {
+                    text: &self.prefix,
+                    style_desc: self.prefix_style_desc,
+                }
+            }
+
+            fn delimiter(&self) -> $crate::leaf::Affix<'_> {
+                $crate::leaf::Affix {
+                    text: &self.delimiter,
+                    style_desc: self.delimiter_style_desc,
+                }
+            }
+
+            fn suffix(&self) -> $crate::leaf::Affix<'_> {
+                $crate::leaf::Affix {
+                    text: &self.suffix,
+                    style_desc: self.suffix_style_desc,
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use impl_leaf_format;
+
+/// Write the leaf `value` with `format`: its indentation, the prefix, the
+/// already styled `body` between delimiters, and the suffix.
+pub fn write_leaf<T, F, W>(
+    value: &T,
+    sink: &mut W,
+    format: &F,
+    ctx: &mut Context,
+    body: &str,
+) -> std::fmt::Result
+where
+    T: ColorDisplay<F> + ?Sized,
+    F: LeafFormat,
+    W: Write,
+{
+    write_leaf_with(value, sink, format, ctx, body, |sink, ctx, delimited| {
+        ctx.write(sink, &delimited)
+    })
+}
+
+/// Like `write_leaf()`, but the body between its delimiters is written by
+/// `write_delimited`, e.g. to wrap it.  The prefix has been written when
+/// `write_delimited` is called.
+pub fn write_leaf_with<T, F, W>(
+    value: &T,
+    sink: &mut W,
+    format: &F,
+    ctx: &mut Context,
+    body: &str,
+    write_delimited: impl FnOnce(&mut W, &mut Context, String) -> std::fmt::Result,
+) -> std::fmt::Result
+where
+    T: ColorDisplay<F> + ?Sized,
+    F: LeafFormat,
+    W: Write,
+{
+    let indent = ctx.indentation(format.indent());
+    value.write_indentation_in(sink, indent, format, ctx)?;
+    let paint = |ctx: &Context, affix: Affix| ctx.paint(affix.text, affix.style_desc);
+    let prefix = paint(ctx, format.prefix());
+    ctx.write(sink, &prefix)?;
+    let d = paint(ctx, format.delimiter());
+    write_delimited(sink, ctx, format!("{d}{body}{d}"))?;
+    let suffix = paint(ctx, format.suffix());
+    ctx.write(sink, &suffix)
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{ansi, Color};
+    use crate::bool::BoolFormat;
+    use crate::char::CharFormat;
+    use crate::primitive::*;
+    use crate::str::StrFormat;
+    use super::*;
+
+    /// Renders `value` in `format`.
+    fn render<T, F>(value: &T, format: F, ctx: &mut Context) -> String
+    where
+        T: ColorDisplay<F> + ?Sized,
+        F: LeafFormat,
+    {
+        let mut sink = String::new();
+        value.color_fmt_in(&mut sink, &format, ctx).unwrap();
+        sink
+    }
+
+    /// Tests that every leaf format writes its indentation and affixes the
+    /// same way around the body `$body` of `$value`.
+    macro_rules! leaf_conformance_tests {
+        ($($name:ident: $value:expr, $format:ty, $body:expr;)*) => {
+            $(
+                #[test]
+                fn $name() {
+                    let affixed = |format: $format| format.with_affixes();
+                    let value = $value;
+
+                    // At the start of a line, nested one level deep.
+                    let mut ctx = Context { depth: 2, ..Context::default() };
+                    let monochrome = render(&value, affixed(<$format>::monochrome(1)), &mut ctx);
+                    assert_eq!(monochrome, format!("        <|{}|>", $body));
+                    assert_eq!(ctx.column, ansi::visible_width(&monochrome));
+
+                    // After other text on the line, only the format's own
+                    // indentation is written.
+                    let mut ctx = Context { depth: 2, column: 3, ..Context::default() };
+                    let monochrome = render(&value, affixed(<$format>::monochrome(1)), &mut ctx);
+                    assert_eq!(monochrome, format!("    <|{}|>", $body));
+
+                    // The punctuation is painted in its own style.
+                    let mut ctx = Context::default();
+                    let colored = render(&value, affixed(<$format>::colored(0)), &mut ctx);
+                    let punctuation = |text: &str| Color::White.dimmed().paint(text).to_string();
+                    assert!(colored.starts_with(&(punctuation("<") + &punctuation("|"))));
+                    assert!(colored.ends_with(&(punctuation("|") + &punctuation(">"))));
+                    assert_eq!(ansi::strip_escapes(&colored), format!("<|{}|>", $body));
+                }
+            )*
+        };
+    }
+
+    /// Formats with the prefix `<`, the delimiter `|` and the suffix `>`.
+    trait WithAffixes {
+        fn with_affixes(self) -> Self;
+    }
+
+    macro_rules! impl_with_affixes {
+        ($($format:ty),* $(,)?) => {
+            $(
+                impl WithAffixes for $format {
+                    fn with_affixes(self) -> Self {
+                        Self {
+                            prefix: "<".into(),
+                            delimiter: "|".into(),
+                            suffix: ">".into(),
+                            ..self
+                        }
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_with_affixes! {
+        BoolFormat, CharFormat, StrFormat,
+        I8Format, I16Format, I32Format, I64Format, I128Format, IsizeFormat,
+        U8Format, U16Format, U32Format, U64Format, U128Format, UsizeFormat,
+        F32Format, F64Format,
+    }
+
+    leaf_conformance_tests! {
+        bool_leaf: true, BoolFormat, "true";
+        char_leaf: 'c', CharFormat, "c";
+        str_leaf: "text", StrFormat, "text";
+        i8_leaf: -8i8, I8Format, "-8";
+        i16_leaf: 16i16, I16Format, "16";
+        i32_leaf: 32i32, I32Format, "32";
+        i64_leaf: 64i64, I64Format, "64";
+        i128_leaf: 128i128, I128Format, "128";
+        isize_leaf: 0isize, IsizeFormat, "0";
+        u8_leaf: 8u8, U8Format, "8";
+        u16_leaf: 16u16, U16Format, "16";
+        u32_leaf: 32u32, U32Format, "32";
+        u64_leaf: 64u64, U64Format, "64";
+        u128_leaf: 128u128, U128Format, "128";
+        usize_leaf: 1usize, UsizeFormat, "1";
+        f32_leaf: 0.5f32, F32Format, "0.5";
+        f64_leaf: 2.25f64, F64Format, "2.25";
+    }
+}
@@ -1,6 +1,8 @@
-//!
+//! A trait-based, `std::fmt::Display`-like architecture for coloring
+//! as well as format printing datastructures.
 
 pub use ansi_term::Color;
+use std::borrow::Cow;
 use std::fmt::Write;
 
 pub trait ColorDisplay<F> {
@@ -13,6 +15,21 @@ pub trait ColorDisplay<F> {
         format: &F,
     ) -> std::fmt::Result;
 
+    /// Like `color_fmt()`, but as part of a larger rendering described
+    /// by `ctx`, e.g. as an item of a collection.  Collections call this
+    /// method rather than `color_fmt()` on their items so that state like
+    /// the current nesting depth is propagated while recursing.
+    /// The default implementation ignores `ctx`.
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &F,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        let _ = ctx;
+        self.color_fmt(sink, format)
+    }
+
     /// Utility method to simpify writing the proper amount of indentation.
     /// In order to print the right indentation token, it takes into account
     /// the implementing type as well as the format type `F`.
@@ -28,6 +45,20 @@ pub trait ColorDisplay<F> {
         Ok(())
     }
 
+    /// Like `write_indentation()`, but also keeps track of the column of `ctx`,
+    /// and honors the indentation token of `ctx`, if any.
+    fn write_indentation_in(
+        &self,
+        sink: &mut impl Write,
+        count: u16,
+        _: &F,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        let token = ctx.indent_token_or(<Self as ColorDisplay<F>>::INDENTATION);
+        let indentation = token.repeat(count as usize);
+        ctx.write(sink, &indentation)
+    }
+
     /// Utility method to simpify writing newlines.
     /// In order to print the right newline character(s), it takes into
     /// account the implementing type as well as the format type `F`.
@@ -42,47 +73,404 @@ pub trait ColorDisplay<F> {
         }
         Ok(())
     }
+
+    /// Like `write_newlines()`, but also keeps track of the column of `ctx`,
+    /// and honors the newline token of `ctx`, if any.
+    fn write_newlines_in(
+        &self,
+        sink: &mut impl Write,
+        count: u16,
+        _: &F,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        let token = ctx.newline_token_or(<Self as ColorDisplay<F>>::NEWLINE);
+        let newlines = token.repeat(count as usize);
+        ctx.write(sink, &newlines)
+    }
+}
+
+/// The state of a rendering that is threaded through nested `color_fmt_in()`
+/// calls.  A fresh `Context` is used by `color_fmt()`.
+#[derive(Clone)]
+pub struct Context {
+    /// The number of collections enclosing the value being rendered.
+    pub depth: u16,
+    /// The number of indentation levels added per level of nesting.
+    pub indent_unit: u16,
+    /// The column of the sink that is being written to,
+    /// i.e. the visible width of the text on the current line.
+    pub column: usize,
+    /// The maximum depth of the values that are written.  Collections at this
+    /// depth write a placeholder instead of their items.
+    pub max_depth: Option<u16>,
+    /// The token written for each level of indentation.  When `None`, the
+    /// `ColorDisplay::INDENTATION` of the type being rendered is used.
+    pub indent_token: Option<Cow<'static, str>>,
+    /// The token written for each newline.  When `None`, the
+    /// `ColorDisplay::NEWLINE` of the type being rendered is used.
+    pub newline_token: Option<Cow<'static, str>>,
+    /// The tree guides written by enclosing collections with a tree layout,
+    /// to be repeated at the start of every line of a nested collection.
+    pub(crate) guides: Vec<String>,
+    /// The combined overlay of the enclosing collections, see
+    /// `Context::leaf_style()`.
+    pub style_overlay: StyleOverlay,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self {
+            depth: 0,
+            indent_unit: 1,
+            column: 0,
+            max_depth: None,
+            indent_token: None,
+            newline_token: None,
+            guides: vec![],
+            style_overlay: StyleOverlay::INHERIT,
+        }
+    }
 }
 
+impl Context {
+    /// The number of indentation levels for a value with a format that
+    /// specifies `indent` levels.  The items of an outermost collection are
+    /// at depth 1 and are indented by `indent` levels only, and every further
+    /// level of nesting adds `self.indent_unit` levels.  Values that don't
+    /// start a new line are indented by `indent` levels only, too.
+    pub fn indentation(&self, indent: u16) -> u16 {
+        if self.column > 0 {
+            return indent;
+        }
+        self.line_indentation(indent)
+    }
+
+    /// Like `indentation()`, but for a value that starts a new line.
+    pub fn line_indentation(&self, indent: u16) -> u16 {
+        let nesting = self.depth.saturating_sub(1);
+        indent.saturating_add(nesting.saturating_mul(self.indent_unit))
+    }
+
+    /// The text that the continuation lines of a multi-line value with a
+    /// format that specifies `indent` levels start with: `line_indentation
+    /// (indent)` times `token`, or inside a tree, where the guides already
+    /// encode the depth, the guides followed by `indent` times `token`.
+    pub fn continuation(&self, indent: u16, token: &str) -> String {
+        if self.guides.is_empty() {
+            return token.repeat(self.line_indentation(indent) as usize);
+        }
+        let mut continuation = self.guides.concat();
+        continuation.push_str(&token.repeat(indent as usize));
+        continuation
+    }
+
+    /// Returns `true` if the items of a collection at the current depth
+    /// would be nested deeper than `self.max_depth`.
+    pub fn max_depth_reached(&self) -> bool {
+        matches!(self.max_depth, Some(max_depth) if self.depth >= max_depth)
+    }
+
+    /// The indentation token of `self`, or `default` if there is none.
+    pub fn indent_token_or<'t>(&'t self, default: &'t str) -> &'t str {
+        self.indent_token.as_deref().unwrap_or(default)
+    }
+
+    /// The newline token of `self`, or `default` if there is none.
+    pub fn newline_token_or<'t>(&'t self, default: &'t str) -> &'t str {
+        self.newline_token.as_deref().unwrap_or(default)
+    }
+
+    /// The style to write a value with style `desc` in, with the overlays
+    /// of the enclosing collections applied.
+    pub fn leaf_style(&self, desc: impl Into<Option<StyleDesc>>) -> ansi_term::Style {
+        compute_leaf_style(self.style_overlay.apply(desc.into()))
+    }
+
+    /// `text` painted in the style that `desc` resolves to, see
+    /// `leaf_style()`.  Empty text stays empty, without escape sequences.
+    pub fn paint(&self, text: &str, desc: Option<StyleDesc>) -> String {
+        match text {
+            "" => String::new(),
+            text => self.leaf_style(desc).paint(text).to_string(),
+        }
+    }
+
+    /// Write `text` to `sink`, and keep track of the column.
+    pub fn write(&mut self, sink: &mut impl Write, text: &str) -> std::fmt::Result {
+        match text.rfind('\n') {
+            Some(idx) => self.column = ansi::visible_width(&text[idx + 1..]),
+            None => self.column += ansi::visible_width(text),
+        }
+        sink.write_str(text)
+    }
+}
+
+/// The text in formats, like prefixes and separators, which is usually
+/// `Cow::Borrowed` from a literal, but can be built at runtime as well.
+pub type Text = Cow<'static, str>;
+
 pub trait Format {
     fn colored(indent: u16) -> Self;
 
     fn monochrome(indent: u16) -> Self;
 }
 
-#[derive(Clone, Copy, PartialEq)]
+/// Describes the style of a piece of text.  A `color` or `background` of
+/// `None` leaves the terminal's default color in place.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct StyleDesc {
-    pub color: Color,
+    pub color: Option<Color>,
+    pub background: Option<Color>,
     pub bold: bool,
     pub italic: bool,
     pub underline: bool,
     pub dimmed: bool,
+    pub blink: bool,
+    pub reverse: bool,
+    pub hidden: bool,
+    pub strikethrough: bool,
+}
+
+impl StyleDesc {
+    /// The style without any colors or attributes.
+    pub const PLAIN: StyleDesc = StyleDesc {
+        color: None,
+        background: None,
+        bold: false,
+        italic: false,
+        underline: false,
+        dimmed: false,
+        blink: false,
+        reverse: false,
+        hidden: false,
+        strikethrough: false,
+    };
+
+    /// The style of punctuation like prefixes, delimiters and separators
+    /// in colored formats.
+    pub const PUNCTUATION: StyleDesc = StyleDesc::fg(Color::White).dimmed();
+
+    /// The plain style with foreground color `color`, to be refined with
+    /// the builder methods, e.g. `StyleDesc::fg(Color::Red).bold()`.
+    pub const fn fg(color: Color) -> StyleDesc {
+        StyleDesc { color: Some(color), ..Self::PLAIN }
+    }
+
+    /// The plain style with background color `color`.
+    pub const fn bg(color: Color) -> StyleDesc {
+        StyleDesc { background: Some(color), ..Self::PLAIN }
+    }
+
+    pub const fn on(self, background: Color) -> StyleDesc {
+        StyleDesc { background: Some(background), ..self }
+    }
+
+    pub const fn bold(self) -> StyleDesc {
+        StyleDesc { bold: true, ..self }
+    }
+
+    pub const fn italic(self) -> StyleDesc {
+        StyleDesc { italic: true, ..self }
+    }
+
+    pub const fn underline(self) -> StyleDesc {
+        StyleDesc { underline: true, ..self }
+    }
+
+    pub const fn dimmed(self) -> StyleDesc {
+        StyleDesc { dimmed: true, ..self }
+    }
+
+    pub const fn blink(self) -> StyleDesc {
+        StyleDesc { blink: true, ..self }
+    }
+
+    pub const fn reverse(self) -> StyleDesc {
+        StyleDesc { reverse: true, ..self }
+    }
+
+    pub const fn hidden(self) -> StyleDesc {
+        StyleDesc { hidden: true, ..self }
+    }
+
+    pub const fn strikethrough(self) -> StyleDesc {
+        StyleDesc { strikethrough: true, ..self }
+    }
+
+    /// The style of text with style `self` that `over` is layered over:
+    /// the colors of `over` where it has them, with the attributes of both.
+    pub fn layered(self, over: StyleDesc) -> StyleDesc {
+        StyleDesc {
+            color: over.color.or(self.color),
+            background: over.background.or(self.background),
+            bold: self.bold || over.bold,
+            italic: self.italic || over.italic,
+            underline: self.underline || over.underline,
+            dimmed: self.dimmed || over.dimmed,
+            blink: self.blink || over.blink,
+            reverse: self.reverse || over.reverse,
+            hidden: self.hidden || over.hidden,
+            strikethrough: self.strikethrough || over.strikethrough,
+        }
+    }
+}
+
+impl Default for StyleDesc {
+    fn default() -> Self {
+        Self::PLAIN
+    }
+}
+
+/// How a `StyleOverlay` changes one attribute of the styles below it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Cascade<T> {
+    /// Keep the attribute of the style below.
+    #[default]
+    Inherit,
+    /// Replace the attribute of the style below.
+    Set(T),
+    /// Remove the attribute of the style below.
+    Clear,
+}
+
+impl<T> Cascade<T> {
+    /// `self`, or `below` if `self` inherits.
+    pub fn or(self, below: Cascade<T>) -> Cascade<T> {
+        match self {
+            Self::Inherit => below,
+            _ => self,
+        }
+    }
+
+    /// The attribute of a style that has attribute `below` before `self`
+    /// is applied to it.
+    pub fn resolve(self, below: Option<T>) -> Option<T> {
+        match self {
+            Self::Inherit => below,
+            Self::Set(value) => Some(value),
+            Self::Clear => None,
+        }
+    }
+}
+
+/// A style that a collection layers over the styles of all of its
+/// descendants, e.g. to dim stale data.  The overlays of nested collections
+/// are combined, with the innermost one taking precedence per attribute.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StyleOverlay {
+    pub color: Cascade<Color>,
+    pub background: Cascade<Color>,
+    pub bold: Cascade<bool>,
+    pub italic: Cascade<bool>,
+    pub underline: Cascade<bool>,
+    pub dimmed: Cascade<bool>,
+    pub blink: Cascade<bool>,
+    pub reverse: Cascade<bool>,
+    pub hidden: Cascade<bool>,
+    pub strikethrough: Cascade<bool>,
+}
+
+impl StyleOverlay {
+    /// The overlay that leaves every attribute as it is.
+    pub const INHERIT: StyleOverlay = StyleOverlay {
+        color: Cascade::Inherit,
+        background: Cascade::Inherit,
+        bold: Cascade::Inherit,
+        italic: Cascade::Inherit,
+        underline: Cascade::Inherit,
+        dimmed: Cascade::Inherit,
+        blink: Cascade::Inherit,
+        reverse: Cascade::Inherit,
+        hidden: Cascade::Inherit,
+        strikethrough: Cascade::Inherit,
+    };
+
+    /// The overlay of a collection with overlay `self` that is nested in
+    /// collections with the combined overlay `outer`.
+    pub fn or(self, outer: StyleOverlay) -> StyleOverlay {
+        StyleOverlay {
+            color: self.color.or(outer.color),
+            background: self.background.or(outer.background),
+            bold: self.bold.or(outer.bold),
+            italic: self.italic.or(outer.italic),
+            underline: self.underline.or(outer.underline),
+            dimmed: self.dimmed.or(outer.dimmed),
+            blink: self.blink.or(outer.blink),
+            reverse: self.reverse.or(outer.reverse),
+            hidden: self.hidden.or(outer.hidden),
+            strikethrough: self.strikethrough.or(outer.strikethrough),
+        }
+    }
+
+    /// The effective style of a value with style `desc` below `self`.
+    /// Values without a style are treated as `StyleDesc::PLAIN` unless
+    /// `self` inherits every attribute.
+    pub fn apply(&self, desc: Option<StyleDesc>) -> Option<StyleDesc> {
+        if *self == Self::INHERIT {
+            return desc;
+        }
+        let desc = desc.unwrap_or_default();
+        let flag = |cascade: Cascade<bool>, below: bool| {
+            cascade.resolve(Some(below)).unwrap_or_default()
+        };
+        Some(StyleDesc {
+            color: self.color.resolve(desc.color),
+            background: self.background.resolve(desc.background),
+            bold: flag(self.bold, desc.bold),
+            italic: flag(self.italic, desc.italic),
+            underline: flag(self.underline, desc.underline),
+            dimmed: flag(self.dimmed, desc.dimmed),
+            blink: flag(self.blink, desc.blink),
+            reverse: flag(self.reverse, desc.reverse),
+            hidden: flag(self.hidden, desc.hidden),
+            strikethrough: flag(self.strikethrough, desc.strikethrough),
+        })
+    }
 }
 
 pub fn compute_leaf_style(
     desc: impl Into<Option<StyleDesc>>
 ) -> ansi_term::Style {
-    if let Some(desc) = desc.into() {
-        let style = desc.color.normal();
-        let style = if desc.bold      { style.bold()      } else { style };
-        let style = if desc.italic    { style.italic()    } else { style };
-        let style = if desc.underline { style.underline() } else { style };
-        let style = if desc.dimmed    { style.dimmed()    } else { style };
-        style
-    } else {
-        ansi_term::Style::default()
-    }
+    let Some(desc) = desc.into() else {
+        return ansi_term::Style::default();
+    };
+    let style = ansi_term::Style::new();
+    let style = match desc.color      { Some(c) => style.fg(c), None => style };
+    let style = match desc.background { Some(c) => style.on(c), None => style };
+    let style = if desc.bold          { style.bold()          } else { style };
+    let style = if desc.italic        { style.italic()        } else { style };
+    let style = if desc.underline     { style.underline()     } else { style };
+    let style = if desc.dimmed        { style.dimmed()        } else { style };
+    let style = if desc.blink         { style.blink()         } else { style };
+    let style = if desc.reverse       { style.reverse()       } else { style };
+    let style = if desc.hidden        { style.hidden()        } else { style };
+    if desc.strikethrough { style.strikethrough() } else { style }
 }
 
+pub mod ansi;
+pub mod assert;
 pub mod bool;
+pub mod bytes;
 pub mod char;
+pub mod diff;
+pub mod dynamic;
+pub mod error;
 pub mod btree_map;
 pub mod btree_set;
 pub mod hash_map;
 pub mod hash_set;
+pub mod highlight;
+pub mod indent;
+pub mod layout;
+pub mod leaf;
+pub mod option;
 pub mod primitive;
 pub mod result;
 pub mod slice;
+pub mod snapshot;
 pub mod str;
+pub mod style;
+pub mod table;
 pub mod vec;
 pub mod vec_deque;
+pub mod wrap;
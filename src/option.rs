@@ -0,0 +1,98 @@
+//! `ColorDisplay` support for `Option<T>`.
+
+use super::*;
+
+impl<TF, T> ColorDisplay<OptionFormat<TF>> for Option<T>
+where
+    T: ColorDisplay<TF>,
+{
+    fn color_fmt(
+        &self,
+        sink: &mut impl Write,
+        format: &OptionFormat<TF>,
+    ) -> std::fmt::Result {
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &OptionFormat<TF>,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        match self {
+            Some(some) => {
+                let prefix = ctx.paint(&format.some_prefix, format.some_prefix_style_desc);
+                ctx.write(sink, &prefix)?;
+                some.color_fmt_in(sink, &format.some_format, ctx)
+            },
+            None => {
+                let indent = ctx.indentation(format.indent);
+                self.write_indentation_in(sink, indent, format, ctx)?;
+                let style = ctx.leaf_style(format.none_style_desc);
+                ctx.write(sink, &style.paint(format.none_text.as_ref()).to_string())
+            },
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct OptionFormat<TF> {
+    /// The indentation of `none_text`.
+    pub indent: u16,
+    pub some_prefix: Text,
+    pub some_prefix_style_desc: Option<StyleDesc>,
+    pub some_format: TF,
+    pub none_text: Text,
+    pub none_style_desc: Option<StyleDesc>,
+}
+
+impl<TF: Format> Format for OptionFormat<TF> {
+    fn colored(indent: u16) -> Self {
+        Self {
+            indent,
+            some_prefix: "".into(),
+            some_prefix_style_desc: Some(StyleDesc::PUNCTUATION),
+            some_format: TF::colored(indent),
+            none_text: "None".into(),
+            none_style_desc: Some(StyleDesc::fg(Color::White).dimmed().italic()),
+        }
+    }
+
+    fn monochrome(indent: u16) -> Self {
+        Self {
+            indent,
+            some_prefix: "".into(),
+            some_prefix_style_desc: None,
+            some_format: TF::monochrome(indent),
+            none_text: "None".into(),
+            none_style_desc: None,
+        }
+    }
+}
+
+impl<TF: Format> Default for OptionFormat<TF> {
+    fn default() -> Self {
+        Self::colored(0)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{primitive::U8Format, Color, ColorDisplay};
+    use super::*;
+
+    #[test]
+    fn color_fmt() -> std::fmt::Result {
+        let mut sink = String::with_capacity(1024);
+        Some(7u8).color_fmt(&mut sink, &OptionFormat::<U8Format>::colored(1))?;
+        assert_eq!(sink, format!("    {}", Color::Blue.bold().paint("7")));
+
+        let mut sink = String::with_capacity(1024);
+        None::<u8>.color_fmt(&mut sink, &OptionFormat::<U8Format>::colored(1))?;
+        let none = Color::White.italic().dimmed().paint("None");
+        assert_eq!(sink, format!("    {none}"));
+        Ok(())
+    }
+}
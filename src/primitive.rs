@@ -1,6 +1,7 @@
-//!
+//! `ColorDisplay` support for the numeric primitive types.
 
-use crate::{compute_leaf_style, Color, ColorDisplay, Format, StyleDesc};
+use crate::{Color, ColorDisplay, Context, Format, StyleDesc, Text};
+use crate::leaf::{impl_leaf_format, write_leaf};
 use std::fmt::Write;
 
 macro_rules! impl_ColorDisplay_and_add_wrappers_for_numeric_types {
@@ -12,44 +13,79 @@ macro_rules! impl_ColorDisplay_and_add_wrappers_for_numeric_types {
                     sink: &mut impl Write,
                     format: &[<$type:camel Format>],
                 ) -> std::fmt::Result {
-                    self.write_indentation(sink, format.indent, format)?;
-                    write!(sink, "{}", format.prefix)?;
-                    let style = compute_leaf_style(format.style_desc);
-                    write!(sink, "{}", style.paint(format!("{self}")))
+                    self.color_fmt_in(sink, format, &mut Context::default())
+                }
+
+                fn color_fmt_in(
+                    &self,
+                    sink: &mut impl Write,
+                    format: &[<$type:camel Format>],
+                    ctx: &mut Context,
+                ) -> std::fmt::Result {
+                    let style = ctx.leaf_style(format.style_desc);
+                    let text = style.paint(format!("{self}")).to_string();
+                    write_leaf(self, sink, format, ctx, &text)
                 }
             }
 
-            #[derive(Clone, Copy)]
+            #[derive(Clone)]
             pub struct [<$type:camel Format>] {
                 pub indent: u16,
-                pub prefix: &'static str,
+                pub prefix: Text,
+                pub prefix_style_desc: Option<StyleDesc>,
+                pub delimiter: Text,
+                pub delimiter_style_desc: Option<StyleDesc>,
+                pub suffix: Text,
+                pub suffix_style_desc: Option<StyleDesc>,
                 pub style_desc: Option<StyleDesc>,
             }
 
-            impl Format for [<$type:camel Format>] {
-                fn colored(indent: u16) -> Self {
+            impl_leaf_format!([<$type:camel Format>]);
+
+            impl [<$type:camel Format>] {
+                pub const fn colored(indent: u16) -> Self {
                     Self {
                         indent,
-                        prefix: "",
-                        style_desc: Some(StyleDesc {
-                            color: Color::Blue,
-                            bold: true,
-                            italic: false,
-                            underline: false,
-                            dimmed: false,
-                        }),
+                        prefix: Text::Borrowed(""),
+                        prefix_style_desc: Some(StyleDesc::PUNCTUATION),
+                        delimiter: Text::Borrowed(""),
+                        delimiter_style_desc: Some(StyleDesc::PUNCTUATION),
+                        suffix: Text::Borrowed(""),
+                        suffix_style_desc: Some(StyleDesc::PUNCTUATION),
+                        style_desc: Some(StyleDesc::fg(Color::Blue).bold()),
                     }
                 }
 
-                fn monochrome(indent: u16) -> Self {
+                pub const fn monochrome(indent: u16) -> Self {
                     Self {
                         indent,
-                        prefix: "",
+                        prefix: Text::Borrowed(""),
+                        prefix_style_desc: None,
+                        delimiter: Text::Borrowed(""),
+                        delimiter_style_desc: None,
+                        suffix: Text::Borrowed(""),
+                        suffix_style_desc: None,
                         style_desc: None,
                     }
                 }
             }
 
+            impl Format for [<$type:camel Format>] {
+                fn colored(indent: u16) -> Self {
+                    Self::colored(indent)
+                }
+
+                fn monochrome(indent: u16) -> Self {
+                    Self::monochrome(indent)
+                }
+            }
+
+            impl Default for [<$type:camel Format>] {
+                fn default() -> Self {
+                    Self::colored(0)
+                }
+            }
+
         )*
     }}
 }
@@ -75,14 +111,13 @@ mod test {
                         let mut sink = String::with_capacity(1024);
                         num.color_fmt(&mut sink, &[<$type:camel Format>] {
                             indent: 0,
-                            prefix: "",
-                            style_desc: Some(StyleDesc {
-                                color: Color::Yellow,
-                                bold: false,
-                                italic: true,
-                                underline: false,
-                                dimmed: true,
-                            }),
+                            prefix: "".into(),
+                            prefix_style_desc: None,
+                            delimiter: "".into(),
+                            delimiter_style_desc: None,
+                            suffix: "".into(),
+                            suffix_style_desc: None,
+                            style_desc: Some(StyleDesc::fg(Color::Yellow).italic().dimmed()),
                         })?;
                         let expected = format!(
                             "{}", Color::Yellow.italic().dimmed().paint("42")
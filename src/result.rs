@@ -1,5 +1,4 @@
-//!
-//!
+//! `ColorDisplay` support for `Result<O, E>`.
 
 use super::*;
 use std::result::Result;
@@ -13,47 +12,208 @@ where
         &self,
         sink: &mut impl Write,
         format: &ResultFormat<OF, EF>,
+    ) -> std::fmt::Result {
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &ResultFormat<OF, EF>,
+        ctx: &mut Context,
     ) -> std::fmt::Result {
         match self {
-            // TODO: paint `ok` in green and `err` in red
-            Ok(ok) => {
-                write!(sink, "{}", format.ok_prefix)?;
-                ok.color_fmt(sink, &format.ok_format)?;
-            },
-            Err(err) => {
-                write!(sink, "{}", format.err_prefix)?;
-                err.color_fmt(sink, &format.err_format)?;
-            },
+            Ok(ok) => write_variant(
+                ok, sink, ctx,
+                (&format.ok_prefix, format.ok_prefix_style_desc),
+                (&format.ok_suffix, format.ok_suffix_style_desc),
+                format.ok_style_overlay,
+                &format.ok_format,
+            ),
+            Err(err) => write_variant(
+                err, sink, ctx,
+                (&format.err_prefix, format.err_prefix_style_desc),
+                (&format.err_suffix, format.err_suffix_style_desc),
+                format.err_style_overlay,
+                &format.err_format,
+            ),
         }
-        Ok(())
     }
 }
 
-#[derive(Clone, Copy)]
-pub struct ResultFormat<OF, EF> {
+/// Write the value of a variant between its markers, with `overlay` layered
+/// over the styles of the value.
+fn write_variant<T, F>(
+    value: &T,
+    sink: &mut impl Write,
+    ctx: &mut Context,
+    (prefix, prefix_style_desc): (&str, Option<StyleDesc>),
+    (suffix, suffix_style_desc): (&str, Option<StyleDesc>),
+    overlay: StyleOverlay,
+    format: &F,
+) -> std::fmt::Result
+where
+    T: ColorDisplay<F> + ?Sized,
+{
+    let prefix = ctx.paint(prefix, prefix_style_desc);
+    ctx.write(sink, &prefix)?;
+    let outer = ctx.style_overlay;
+    ctx.style_overlay = overlay.or(outer);
+    let written = value.color_fmt_in(sink, format, ctx);
+    ctx.style_overlay = outer;
+    written?;
+    let suffix = ctx.paint(suffix, suffix_style_desc);
+    ctx.write(sink, &suffix)
+}
+
+/// The sets of markers that `Ok` and `Err` values are written between.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResultMarkers {
+    /// `✅ ` and `❌ `.
+    Emoji,
+    /// `✓ ` and `✗ `.
+    Symbols,
+    /// `OK: ` and `ERR: `, for consoles that can't display Unicode.
+    Ascii,
+    /// `Ok(..)` and `Err(..)`, like `Debug`.
+    Rust,
+}
+
+impl ResultMarkers {
+    /// `Emoji` if the console can display Unicode, `Ascii` otherwise.
+    pub fn detect() -> Self {
+        match unicode_supported() {
+            true => Self::Emoji,
+            false => Self::Ascii,
+        }
+    }
+
+    /// The prefixes and suffixes of `Ok` and `Err` values.
+    pub fn texts(self) -> MarkerTexts {
+        let (ok_prefix, ok_suffix, err_prefix, err_suffix) = match self {
+            Self::Emoji => ("✅ ", "", "❌ ", ""),
+            Self::Symbols => ("✓ ", "", "✗ ", ""),
+            Self::Ascii => ("OK: ", "", "ERR: ", ""),
+            Self::Rust => ("Ok(", ")", "Err(", ")"),
+        };
+        MarkerTexts { ok_prefix, ok_suffix, err_prefix, err_suffix }
+    }
+}
+
+/// The texts of a set of `ResultMarkers`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarkerTexts {
     pub ok_prefix: &'static str,
-    pub ok_format: OF,
+    pub ok_suffix: &'static str,
     pub err_prefix: &'static str,
+    pub err_suffix: &'static str,
+}
+
+/// Returns `true` if the console is likely to display Unicode, i.e. if the
+/// encoding of the locale is UTF-8, or if running in Windows Terminal.
+fn unicode_supported() -> bool {
+    if cfg!(windows) && std::env::var_os("WT_SESSION").is_some() {
+        return true;
+    }
+    // The first of these variables that is set determines the encoding.
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty());
+    locale.is_some_and(|locale| {
+        let locale = locale.to_ascii_lowercase();
+        locale.ends_with(".utf-8") || locale.ends_with(".utf8")
+    })
+}
+
+#[derive(Clone)]
+pub struct ResultFormat<OF, EF> {
+    pub ok_prefix: Text,
+    pub ok_prefix_style_desc: Option<StyleDesc>,
+    pub ok_suffix: Text,
+    pub ok_suffix_style_desc: Option<StyleDesc>,
+    /// Layered over the styles of `Ok` values, e.g. to tint them green.
+    pub ok_style_overlay: StyleOverlay,
+    pub ok_format: OF,
+    pub err_prefix: Text,
+    pub err_prefix_style_desc: Option<StyleDesc>,
+    pub err_suffix: Text,
+    pub err_suffix_style_desc: Option<StyleDesc>,
+    pub err_style_overlay: StyleOverlay,
     pub err_format: EF,
 }
 
+impl<OF, EF> ResultFormat<OF, EF> {
+    /// Use the prefixes and suffixes of `markers`.
+    pub fn with_markers(self, markers: ResultMarkers) -> Self {
+        let texts = markers.texts();
+        Self {
+            ok_prefix: texts.ok_prefix.into(),
+            ok_suffix: texts.ok_suffix.into(),
+            err_prefix: texts.err_prefix.into(),
+            err_suffix: texts.err_suffix.into(),
+            ..self
+        }
+    }
+
+    /// Tint `Ok` and `Err` values in the colors of their prefixes.
+    pub fn tinted(self) -> Self {
+        let tint = |desc: Option<StyleDesc>| StyleOverlay {
+            color: desc.and_then(|desc| desc.color).map_or(Cascade::Inherit, Cascade::Set),
+            ..StyleOverlay::INHERIT
+        };
+        Self {
+            ok_style_overlay: tint(self.ok_prefix_style_desc),
+            err_style_overlay: tint(self.err_prefix_style_desc),
+            ..self
+        }
+    }
+}
+
 impl<OF: Format, EF: Format> Format for ResultFormat<OF, EF> {
+    /// With `ResultMarkers::Emoji`, in green and red.  Use
+    /// `with_markers(ResultMarkers::detect())` to adapt to the console.
     fn colored(indent: u16) -> Self {
+        let ok_style_desc = Some(StyleDesc::fg(Color::Green).bold());
+        let err_style_desc = Some(StyleDesc::fg(Color::Red).bold());
         Self {
-            ok_prefix: "✅ ",
+            ok_prefix: "".into(),
+            ok_prefix_style_desc: ok_style_desc,
+            ok_suffix: "".into(),
+            ok_suffix_style_desc: ok_style_desc,
+            ok_style_overlay: StyleOverlay::INHERIT,
             ok_format: OF::colored(indent),
-            err_prefix: "❌ ",
+            err_prefix: "".into(),
+            err_prefix_style_desc: err_style_desc,
+            err_suffix: "".into(),
+            err_suffix_style_desc: err_style_desc,
+            err_style_overlay: StyleOverlay::INHERIT,
             err_format: EF::colored(indent),
-        }
+        }.with_markers(ResultMarkers::Emoji)
     }
 
+    /// With `ResultMarkers::Ascii`, which any console and log can display.
+    /// Use `with_markers(ResultMarkers::detect())` to adapt to the console.
     fn monochrome(indent: u16) -> Self {
         Self {
-            ok_prefix: "✅ ",
+            ok_prefix: "".into(),
+            ok_prefix_style_desc: None,
+            ok_suffix: "".into(),
+            ok_suffix_style_desc: None,
+            ok_style_overlay: StyleOverlay::INHERIT,
             ok_format: OF::monochrome(indent),
-            err_prefix: "❌ ",
+            err_prefix: "".into(),
+            err_prefix_style_desc: None,
+            err_suffix: "".into(),
+            err_suffix_style_desc: None,
+            err_style_overlay: StyleOverlay::INHERIT,
             err_format: EF::monochrome(indent),
-        }
+        }.with_markers(ResultMarkers::Ascii)
+    }
+}
+
+impl<OF: Format, EF: Format> Default for ResultFormat<OF, EF> {
+    fn default() -> Self {
+        Self::colored(0)
     }
 }
 
@@ -62,38 +222,32 @@ impl<OF: Format, EF: Format> Format for ResultFormat<OF, EF> {
 mod test {
     use crate::{
         Color, ColorDisplay,
+        primitive::U8Format,
         str::StrFormat,
     };
     use super::*;
 
-    #[test]
-    fn ok_color_fmt() -> std::fmt::Result {
-        let ok_result: Result<&str, &str> = Ok("hello");
-        let mut sink = String::with_capacity(1024);
-        ok_result.color_fmt(&mut sink, &ResultFormat{
+    fn format() -> ResultFormat<StrFormat, StrFormat> {
+        ResultFormat {
             ok_format: StrFormat {
-                style_desc: Some(StyleDesc {
-                    color: Color::Green,
-                    bold: false,
-                    italic: false,
-                    underline: false,
-                    dimmed: false,
-                }),
+                style_desc: Some(StyleDesc::fg(Color::Green)),
                 ..StrFormat::colored(0)
             },
             err_format: StrFormat {
-                style_desc: Some(StyleDesc {
-                    color: Color::Red,
-                    bold: true,
-                    italic: false,
-                    underline: false,
-                    dimmed: false,
-                }),
+                style_desc: Some(StyleDesc::fg(Color::Red).bold()),
                 ..StrFormat::colored(0)
             },
             ..ResultFormat::colored(1)
-        })?;
-        let expected = format!("✅ {}", Color::Green.paint("hello"));
+        }.with_markers(ResultMarkers::Emoji)
+    }
+
+    #[test]
+    fn ok_color_fmt() -> std::fmt::Result {
+        let ok_result: Result<&str, &str> = Ok("hello");
+        let mut sink = String::with_capacity(1024);
+        ok_result.color_fmt(&mut sink, &format())?;
+        let check = Color::Green.bold().paint("✅ ");
+        let expected = format!("{check}{}", Color::Green.paint("hello"));
         assert_eq!(sink, expected);
         Ok(())
     }
@@ -102,30 +256,71 @@ mod test {
     fn err_color_fmt() -> std::fmt::Result {
         let ok_result: Result<&str, &str> = Err("oh no");
         let mut sink = String::with_capacity(1024);
-        ok_result.color_fmt(&mut sink, &ResultFormat{
-            ok_format: StrFormat {
-                style_desc: Some(StyleDesc {
-                    color: Color::Green,
-                    bold: false,
-                    italic: false,
-                    underline: false,
-                    dimmed: false,
-                }),
-                ..StrFormat::colored(0)
-            },
-            err_format: StrFormat {
-                style_desc: Some(StyleDesc {
-                    color: Color::Red,
-                    bold: true,
-                    italic: false,
-                    underline: false,
-                    dimmed: false,
-                }),
-                ..StrFormat::colored(0)
-            },
-            ..ResultFormat::colored(1)
-        })?;
-        let expected = format!("❌ {}", Color::Red.bold().paint("oh no"));
+        ok_result.color_fmt(&mut sink, &format())?;
+        let cross = Color::Red.bold().paint("❌ ");
+        let expected = format!("{cross}{}", Color::Red.bold().paint("oh no"));
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn markers_color_fmt() -> std::fmt::Result {
+        let results: [Result<u8, &str>; 2] = [Ok(7), Err("no")];
+        let format = ResultFormat::<U8Format, StrFormat>::monochrome(0);
+        let expected = [
+            (ResultMarkers::Emoji, ["✅ 7", "❌ no"]),
+            (ResultMarkers::Symbols, ["✓ 7", "✗ no"]),
+            (ResultMarkers::Ascii, ["OK: 7", "ERR: no"]),
+            (ResultMarkers::Rust, ["Ok(7)", "Err(no)"]),
+        ];
+        for (markers, expected) in expected {
+            let format = format.clone().with_markers(markers);
+            for (result, expected) in results.iter().zip(expected) {
+                let mut sink = String::with_capacity(1024);
+                result.color_fmt(&mut sink, &format)?;
+                assert_eq!(sink, expected);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn default_markers_color_fmt() -> std::fmt::Result {
+        let mut sink = String::with_capacity(1024);
+        Ok::<u8, &str>(7).color_fmt(&mut sink, &ResultFormat::colored(0))?;
+        let check = Color::Green.bold().paint("✅ ");
+        assert_eq!(sink, format!("{check}{}", Color::Blue.bold().paint("7")));
+
+        let mut sink = String::with_capacity(1024);
+        let format = ResultFormat::<U8Format, StrFormat>::monochrome(0);
+        Err::<u8, &str>("no").color_fmt(&mut sink, &format)?;
+        assert_eq!(sink, "ERR: no");
+        Ok(())
+    }
+
+    #[test]
+    fn tinted_color_fmt() -> std::fmt::Result {
+        let format = ResultFormat::<U8Format, StrFormat>::colored(0)
+            .with_markers(ResultMarkers::Rust)
+            .tinted();
+        let mut sink = String::with_capacity(1024);
+        Err::<u8, &str>("no").color_fmt(&mut sink, &format)?;
+        let expected = format!(
+            "{}{}{}",
+            Color::Red.bold().paint("Err("),
+            Color::Red.paint("no"),
+            Color::Red.bold().paint(")"),
+        );
+        assert_eq!(sink, expected);
+
+        let mut sink = String::with_capacity(1024);
+        Ok::<u8, &str>(7).color_fmt(&mut sink, &format)?;
+        let expected = format!(
+            "{}{}{}",
+            Color::Green.bold().paint("Ok("),
+            Color::Green.bold().paint("7"),
+            Color::Green.bold().paint(")"),
+        );
         assert_eq!(sink, expected);
         Ok(())
     }
@@ -1,6 +1,10 @@
-//!
+//! `ColorDisplay` support for slices `[T]`.
 
 use super::*;
+use crate::layout::{
+    elide, impl_collection_builders, write_items, CollectionFormat,
+    Layout, Truncation,
+};
 
 impl<TF, T> ColorDisplay<SliceFormat<TF>> for [T]
 where
@@ -11,23 +15,35 @@ where
         sink: &mut impl Write,
         format: &SliceFormat<TF>,
     ) -> std::fmt::Result {
-        self.write_newlines(sink, format.prefix_newlines, &format)?;
-        for (idx, item) in self.iter().enumerate() {
-            if idx > 0 {
-                self.write_newlines(sink, format.intersperse_newlines, &format)?;
-            }
-            item.color_fmt(sink, &format.item_format)?;
-        }
-        self.write_newlines(sink, format.suffix_newlines, &format)?;
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &SliceFormat<TF>,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        self.write_newlines_in(sink, format.prefix_newlines, format, ctx)?;
+        write_items(
+            self, sink, format, ctx,
+            elide(self.iter(), format.truncation.limit),
+            |sink, ctx, item| item.color_fmt_in(sink, &format.item_format, ctx),
+        )?;
+        self.write_newlines_in(sink, format.suffix_newlines, format, ctx)?;
         Ok(())
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct SliceFormat<TF> {
     pub prefix_newlines: u16,
     pub intersperse_newlines: u16,
     pub suffix_newlines: u16,
+    pub layout: Layout,
+    pub truncation: Truncation,
+    /// Layered over the styles of the descendants, see `StyleOverlay`.
+    pub style_overlay: StyleOverlay,
     pub item_format: TF,
 }
 
@@ -37,6 +53,9 @@ impl<TF: Format> Format for SliceFormat<TF> {
             prefix_newlines: 0,
             intersperse_newlines: 1,
             suffix_newlines: 0,
+            layout: Layout::Lines,
+            truncation: Truncation::colored(indent),
+            style_overlay: StyleOverlay::INHERIT,
             item_format: TF::colored(indent),
         }
     }
@@ -46,17 +65,47 @@ impl<TF: Format> Format for SliceFormat<TF> {
             prefix_newlines: 0,
             intersperse_newlines: 1,
             suffix_newlines: 0,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(indent),
+            style_overlay: StyleOverlay::INHERIT,
             item_format: TF::monochrome(indent),
         }
     }
 }
 
+impl<TF: Format> Default for SliceFormat<TF> {
+    fn default() -> Self {
+        Self::colored(0)
+    }
+}
+
+impl_collection_builders!(impl<TF> SliceFormat<TF>);
+
+impl<TF> CollectionFormat for SliceFormat<TF> {
+    fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    fn intersperse_newlines(&self) -> u16 {
+        self.intersperse_newlines
+    }
+
+    fn truncation(&self) -> &Truncation {
+        &self.truncation
+    }
+
+    fn style_overlay(&self) -> &StyleOverlay {
+        &self.style_overlay
+    }
+}
+
 
 #[cfg(test)]
 mod test {
     use crate::{
         Color, ColorDisplay,
         primitive::U8Format,
+        vec::VecFormat,
     };
     use super::*;
 
@@ -68,6 +117,9 @@ mod test {
             prefix_newlines: 1,
             intersperse_newlines: 1,
             suffix_newlines: 1,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(0),
+            style_overlay: StyleOverlay::INHERIT,
             item_format: U8Format::colored(1),
         })?;
         let expected = format!(
@@ -79,4 +131,75 @@ mod test {
         assert_eq!(sink, expected);
         Ok(())
     }
+
+    #[test]
+    fn nested_color_fmt() -> std::fmt::Result {
+        let slice: &[Vec<u8>] = &[vec![1, 2], vec![3]];
+        let mut sink = String::with_capacity(1024);
+        slice.color_fmt(&mut sink, &SliceFormat {
+            prefix_newlines: 0,
+            intersperse_newlines: 1,
+            suffix_newlines: 0,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(0),
+            style_overlay: StyleOverlay::INHERIT,
+            item_format: VecFormat {
+                prefix_newlines: 1,
+                intersperse_newlines: 1,
+                suffix_newlines: 0,
+                layout: Layout::Lines,
+                truncation: Truncation::monochrome(0),
+                style_overlay: StyleOverlay::INHERIT,
+                item_format: U8Format::monochrome(1),
+            },
+        })?;
+        // The inner items are indented relative to the outer ones.
+        assert_eq!(sink, "\n        1\n        2\n\n        3");
+
+        let mut sink = String::with_capacity(1024);
+        let mut ctx = Context { indent_unit: 2, ..Context::default() };
+        slice.color_fmt_in(&mut sink, &SliceFormat {
+            prefix_newlines: 0,
+            intersperse_newlines: 1,
+            suffix_newlines: 0,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(0),
+            style_overlay: StyleOverlay::INHERIT,
+            item_format: VecFormat {
+                prefix_newlines: 1,
+                intersperse_newlines: 0,
+                suffix_newlines: 0,
+                layout: Layout::Lines,
+                truncation: Truncation::monochrome(0),
+                style_overlay: StyleOverlay::INHERIT,
+                item_format: U8Format::monochrome(0),
+            },
+        }, &mut ctx)?;
+        // Only the first item of each inner Vec starts a line.
+        assert_eq!(sink, "\n        12\n\n        3");
+        assert_eq!(ctx.column, 9);
+        Ok(())
+    }
+
+    #[test]
+    fn builders_color_fmt() -> std::fmt::Result {
+        let slice: &[Vec<u8>] = &[vec![1, 2, 3], vec![4]];
+        let mut sink = String::with_capacity(1024);
+        let format = SliceFormat::<VecFormat<U8Format>> {
+            item_format: VecFormat::monochrome(0).inline().max_items(2),
+            ..SliceFormat::monochrome(0)
+        };
+        slice.color_fmt(&mut sink, &format.inline())?;
+        // Only the first item starts a line, and is indented for its depth.
+        assert_eq!(sink, "    1, 2, … 1 more, 4");
+
+        let style = StyleDesc::fg(Color::Red).on(Color::Black).bold();
+        assert_eq!(style, StyleDesc {
+            color: Some(Color::Red),
+            background: Some(Color::Black),
+            bold: true,
+            ..StyleDesc::default()
+        });
+        Ok(())
+    }
 }
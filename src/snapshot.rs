@@ -0,0 +1,247 @@
+//! Snapshot tests for colored output: a value is rendered, its escape
+//! sequences are converted to a readable markup, and the result is compared
+//! to a `.snap` file stored next to the test, see `assert_snapshot!`.
+
+use crate::{
+    ansi::{self, Segment},
+    assert::use_color,
+    diff::{ColorDiff, DiffFormat},
+    slice::SliceFormat,
+    str::StrFormat,
+    ColorDisplay, Format,
+};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// The environment variable that, when set to anything other than `0`,
+/// makes snapshot assertions update their `.snap` files in place.
+pub const UPDATE_VAR: &str = "COLOR_YOUR_LIFE_UPDATE_SNAPSHOTS";
+
+/// Asserts that a value rendered with a format matches the snapshot `name`,
+/// stored as `snapshots/<name>.snap` in the directory of the calling file.
+/// Set the `COLOR_YOUR_LIFE_UPDATE_SNAPSHOTS` environment variable to create
+/// or update the snapshot instead.
+///
+/// ```ignore
+/// assert_snapshot!("report", report, ReportFormat::colored(0));
+/// ```
+#[macro_export]
+macro_rules! assert_snapshot {
+    ($name:expr, $value:expr, $format:expr $(,)?) => {
+        $crate::snapshot::assert_snapshot(
+            $crate::snapshot::snapshot_path(env!("CARGO_MANIFEST_DIR"), file!(), $name),
+            &$value,
+            &$format,
+        )
+    };
+}
+
+/// The path of the snapshot `name` for a test in `file`, as given by
+/// `file!()`, which is relative to the crate root or to a workspace root
+/// that is an ancestor of `manifest_dir`.
+pub fn snapshot_path(manifest_dir: &str, file: &str, name: &str) -> PathBuf {
+    let manifest_dir = Path::new(manifest_dir);
+    let file = manifest_dir.ancestors()
+        .map(|root| root.join(file))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| manifest_dir.join(file));
+    let dir = file.parent().unwrap_or(manifest_dir);
+    dir.join("snapshots").join(format!("{name}.snap"))
+}
+
+/// Render `value` with `format`, and compare its markup to the snapshot
+/// at `path`.  Panics with a diff if they don't match.
+#[track_caller]
+pub fn assert_snapshot<T, F>(path: impl AsRef<Path>, value: &T, format: &F)
+where
+    T: ColorDisplay<F> + ?Sized,
+{
+    let mut rendered = String::new();
+    if value.color_fmt(&mut rendered, format).is_err() {
+        panic!("failed to render the value for snapshot {:?}", path.as_ref());
+    }
+    let update = matches!(std::env::var(UPDATE_VAR).as_deref(), Ok(var) if var != "0");
+    if let Err(message) = check_snapshot(path.as_ref(), &to_markup(&rendered), update) {
+        panic!("{message}");
+    }
+}
+
+/// Compare `markup` to the snapshot at `path`, or write it to `path` if
+/// `update` is `true`.  On a mismatch, the error describes the difference.
+pub fn check_snapshot(path: &Path, markup: &str, update: bool) -> Result<(), String> {
+    if update {
+        let written = path.parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(path, format!("{markup}\n")));
+        return written.map_err(|err| format!("failed to write {path:?}: {err}"));
+    }
+    let Ok(stored) = std::fs::read_to_string(path) else {
+        return Err(format!(
+            "snapshot {path:?} doesn't exist, set {UPDATE_VAR}=1 to create it:\n{markup}",
+        ));
+    };
+    let stored = stored.strip_suffix('\n').unwrap_or(&stored);
+    if stored == markup {
+        return Ok(());
+    }
+    let stored: Vec<&str> = stored.lines().collect();
+    let actual: Vec<&str> = markup.lines().collect();
+    let diff_format = if use_color() {
+        DiffFormat::colored()
+    } else {
+        DiffFormat::monochrome()
+    };
+    let mut diff = String::new();
+    let _ = stored.as_slice().color_diff(
+        &actual,
+        &mut diff,
+        &SliceFormat::<StrFormat>::monochrome(0),
+        &diff_format,
+    );
+    Err(format!(
+        "snapshot {path:?} doesn't match (- stored, + actual), \
+         set {UPDATE_VAR}=1 to update it:\n{diff}",
+    ))
+}
+
+/// Replace the SGR escape sequences in `text` with a readable markup, e.g.
+/// `[bold red]` for `"\x1b[1;31m"` and `[/]` for a reset.  Other escape
+/// sequences are written like `[esc 2K]`.
+pub fn to_markup(text: &str) -> String {
+    let mut markup = String::with_capacity(text.len());
+    for segment in ansi::segments(text) {
+        match segment {
+            Segment::Text(text) => markup.push_str(text),
+            Segment::Escape(escape) => {
+                // Unterminated escape sequences are cut short at the end of
+                // `text`, so they are sliced with care.
+                let sgr_params = escape.strip_suffix('m')
+                    .and_then(|escape| escape.get(2..));
+                match sgr_params {
+                    Some(_) if ansi::is_reset(escape) => markup.push_str("[/]"),
+                    Some(params) => {
+                        let _ = write!(markup, "[{}]", sgr_markup(params).join(" "));
+                    },
+                    None => {
+                        let rest = escape.get(2..).unwrap_or_default();
+                        let _ = write!(markup, "[esc {rest}]");
+                    },
+                }
+            },
+        }
+    }
+    markup
+}
+
+/// The names of the attributes set by the parameters of an SGR sequence.
+fn sgr_markup(params: &str) -> Vec<String> {
+    const COLORS: [&str; 8] = [
+        "black", "red", "green", "yellow", "blue", "purple", "cyan", "white",
+    ];
+    let params: Vec<u16> = params.split(';')
+        .map(|param| param.parse().unwrap_or_default())
+        .collect();
+    let mut names = vec![];
+    let mut idx = 0;
+    while idx < params.len() {
+        let name = match params[idx] {
+            0 => "reset".to_string(),
+            1 => "bold".to_string(),
+            2 => "dimmed".to_string(),
+            3 => "italic".to_string(),
+            4 => "underline".to_string(),
+            5 => "blink".to_string(),
+            7 => "reverse".to_string(),
+            8 => "hidden".to_string(),
+            9 => "strikethrough".to_string(),
+            param @ 30..=37 => COLORS[param as usize - 30].to_string(),
+            param @ 40..=47 => format!("on_{}", COLORS[param as usize - 40]),
+            param @ (38 | 48) => {
+                let prefix = if param == 38 { "" } else { "on_" };
+                match params.get(idx + 1..) {
+                    Some([5, n, ..]) => {
+                        idx += 2;
+                        format!("{prefix}fixed({n})")
+                    },
+                    Some([2, r, g, b, ..]) => {
+                        idx += 4;
+                        format!("{prefix}rgb({r},{g},{b})")
+                    },
+                    _ => format!("sgr {param}"),
+                }
+            },
+            param => format!("sgr {param}"),
+        };
+        names.push(name);
+        idx += 1;
+    }
+    names
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        layout::{Layout, TreeCharset, TreeGuides, Truncation},
+        primitive::U8Format,
+        vec::VecFormat,
+        Color, StyleOverlay,
+    };
+    use super::*;
+
+    #[test]
+    fn markup() {
+        let text = format!(
+            "{} {} {}\x1b[2K",
+            Color::Red.bold().paint("a"),
+            Color::Fixed(208).on(Color::RGB(1, 2, 3)).paint("b"),
+            Color::Blue.dimmed().italic().underline().paint("c"),
+        );
+        assert_eq!(
+            to_markup(&text),
+            concat!(
+                "[bold red]a[/] [on_rgb(1,2,3) fixed(208)]b[/] ",
+                "[dimmed italic underline blue]c[/][esc 2K]",
+            ),
+        );
+        // Unterminated escape sequences at the end of the text.
+        assert_eq!(to_markup("x\x1b["), "x[esc ]");
+        assert_eq!(to_markup("x\x1b[é"), "x[esc é]");
+    }
+
+    #[test]
+    fn check_and_update_snapshot() {
+        let dir = std::env::temp_dir().join(format!("cyl-snapshots-{}", std::process::id()));
+        let path = dir.join("nested").join("value.snap");
+        let err = check_snapshot(&path, "[red]a[/]", false).unwrap_err();
+        assert!(err.contains("doesn't exist"), "{err}");
+        check_snapshot(&path, "[red]a[/]\nb", true).unwrap();
+        check_snapshot(&path, "[red]a[/]\nb", false).unwrap();
+        let err = check_snapshot(&path, "[red]a[/]\nc", false).unwrap_err();
+        assert!(err.ends_with("  [red]a[/]\n- b\n+ c"), "{err}");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn tree_snapshot() {
+        let nested: &[Vec<u8>] = &[vec![1, 2], vec![3]];
+        let guides = TreeGuides::colored(TreeCharset::Unicode);
+        crate::assert_snapshot!("tree", *nested, SliceFormat {
+            prefix_newlines: 0,
+            intersperse_newlines: 1,
+            suffix_newlines: 0,
+            layout: Layout::Tree(guides),
+            truncation: Truncation::colored(0),
+            style_overlay: StyleOverlay::INHERIT,
+            item_format: VecFormat {
+                prefix_newlines: 0,
+                intersperse_newlines: 1,
+                suffix_newlines: 0,
+                layout: Layout::Tree(guides),
+                truncation: Truncation::colored(0),
+                style_overlay: StyleOverlay::INHERIT,
+                item_format: U8Format::colored(0),
+            },
+        });
+    }
+}
@@ -1,9 +1,14 @@
-//!
+//! `ColorDisplay` support for `&str`.
 
 use ansi_term::Color;
-use crate::{compute_leaf_style, ColorDisplay, Format, StyleDesc};
+use crate::{ansi, ColorDisplay, Context, Format, StyleDesc, Text};
+use crate::leaf::{impl_leaf_format, write_leaf_with};
+use crate::highlight::{self, Highlights};
+use crate::indent::IndentWriter;
+use crate::wrap::Wrap;
 use std::borrow::Cow;
 use std::fmt::Write;
+use unicode_segmentation::UnicodeSegmentation;
 
 impl ColorDisplay<StrFormat> for &'_ str {
     fn color_fmt(
@@ -11,51 +16,253 @@ impl ColorDisplay<StrFormat> for &'_ str {
         sink: &mut impl Write,
         format: &StrFormat,
     ) -> std::fmt::Result {
-        self.write_indentation(sink, format.indent, format)?;
-        write!(sink, "{}", format.prefix)?;
-        let style = compute_leaf_style(format.style_desc);
-        let d = format.delimiter;
-        write!(sink, "{d}{}{d}", style.paint(Cow::Borrowed(*self)))
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &StrFormat,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        let body = render_body(self, format, ctx);
+        write_leaf_with(self, sink, format, ctx, &body, |sink, ctx, mut value| {
+            // Continuation lines of multi-line text are aligned with the
+            // first, or with the column of the value itself when wrapping.
+            let indentation = ctx.indent_token_or(Self::INDENTATION);
+            let mut continuation = ctx.continuation(format.indent, indentation);
+            if let Some(wrap) = &format.wrap {
+                value = wrap.apply(&value, ctx.column);
+                let width = ansi::visible_width(&continuation);
+                continuation.push_str(&" ".repeat(ctx.column.saturating_sub(width)));
+            }
+            let mut text = String::with_capacity(value.len() + 16);
+            let mut writer = IndentWriter::new(&mut text, continuation)
+                .with_newline(ctx.newline_token_or(Self::NEWLINE));
+            writer.write_str(&value)?;
+            writer.finish()?;
+            ctx.write(sink, &text)
+        })
+    }
+}
+
+/// Render the text between the delimiters: `text` shortened to at most
+/// `format.max_len` grapheme clusters, and escaped according to
+/// `format.escape`, with escape sequences and the ellipsis painted in
+/// `format.escape_style_desc`, and with `format.highlights` layered over it.
+/// The styles are resolved in `ctx`.
+fn render_body(text: &str, format: &StrFormat, ctx: &Context) -> String {
+    // Highlights match the text as a whole, regardless of truncation.
+    let owners = highlight::owners(text, &format.highlights);
+    let (text, truncated) = match format.max_len {
+        Some(max_len) => match text.grapheme_indices(true).nth(max_len) {
+            Some((idx, _)) => (&text[..idx], true),
+            None => (text, false),
+        },
+        None => (text, false),
+    };
+    let mut body = String::with_capacity(text.len() + 16);
+    // Consecutive pieces with the same style are painted as a single run.
+    let mut run = String::with_capacity(text.len());
+    let mut run_style = ansi_term::Style::default();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let idx = text.len() - rest.len();
+        let (piece, len, escaped) = match format.escape.escape_char(c) {
+            Some(escaped) => (escaped, c.len_utf8(), true),
+            // Backslashes must be escaped too, for the escaped delimiter to
+            // be unambiguous.
+            None if format.escape.quotes(&format.delimiter) && c == '\\' => {
+                (Cow::Borrowed("\\\\"), 1, true)
+            },
+            None if format.escape.quotes(&format.delimiter)
+                && rest.starts_with(format.delimiter.as_ref()) => {
+                let escaped = format!("\\{}", format.delimiter);
+                (Cow::Owned(escaped), format.delimiter.len(), true)
+            },
+            None => (Cow::Borrowed(&rest[..c.len_utf8()]), c.len_utf8(), false),
+        };
+        let base = if escaped { format.escape_style_desc } else { format.style_desc };
+        let highlight = owners[idx].map(|rule| &format.highlights[rule]);
+        let style = ctx.leaf_style(highlight::layered(base, highlight));
+        if style != run_style && !run.is_empty() {
+            let _ = write!(body, "{}", run_style.paint(run.as_str()));
+            run.clear();
+        }
+        run_style = style;
+        run.push_str(&piece);
+        rest = &rest[len..];
+    }
+    if !run.is_empty() {
+        let _ = write!(body, "{}", run_style.paint(run.as_str()));
+    }
+    if truncated {
+        let escape_style = ctx.leaf_style(format.escape_style_desc);
+        let _ = write!(body, "{}", escape_style.paint(format.ellipsis.as_ref()));
+    }
+    body
+}
+
+/// Describes how special characters in text are escaped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Escape {
+    /// Write text as-is.
+    None,
+    /// Rust-debug style, e.g. `\n`, `\\` and `\u{1b}`.
+    Debug,
+    /// JSON style, e.g. `\n`, `\\` and `\u001b`.
+    Json,
+    /// Replace control characters with their visible Unicode Control
+    /// Pictures, e.g. `␊` for `\n` and `␛` for `\x1b`.
+    ControlPictures,
+}
+
+impl Escape {
+    /// The escaped form of `c`, or `None` if `c` needs no escaping.
+    pub fn escape_char(self, c: char) -> Option<Cow<'static, str>> {
+        let escaped = match (self, c) {
+            (Self::None, _) => return None,
+            (Self::ControlPictures, c) => match c as u32 {
+                code @ 0x00..=0x1f => char::from_u32(0x2400 + code)?,
+                0x7f => '\u{2421}',
+                code if c.is_control() => {
+                    return Some(Cow::Owned(format!("\\u{{{code:x}}}")));
+                },
+                _ => return None,
+            },
+            (_, '\\') => return Some(Cow::Borrowed("\\\\")),
+            (_, '\n') => return Some(Cow::Borrowed("\\n")),
+            (_, '\r') => return Some(Cow::Borrowed("\\r")),
+            (_, '\t') => return Some(Cow::Borrowed("\\t")),
+            (Self::Debug, '\0') => return Some(Cow::Borrowed("\\0")),
+            (Self::Debug, c) if c.is_control() => {
+                return Some(Cow::Owned(format!("\\u{{{:x}}}", c as u32)));
+            },
+            (Self::Json, '\u{8}') => return Some(Cow::Borrowed("\\b")),
+            (Self::Json, '\u{c}') => return Some(Cow::Borrowed("\\f")),
+            (Self::Json, c) if c.is_control() => {
+                return Some(Cow::Owned(format!("\\u{:04x}", c as u32)));
+            },
+            _ => return None,
+        };
+        Some(Cow::Owned(escaped.to_string()))
+    }
+
+    /// Returns `true` if occurrences of `delimiter` in text are escaped,
+    /// i.e. when escaping and there is a delimiter at all.
+    fn quotes(self, delimiter: &str) -> bool {
+        self != Self::None && !delimiter.is_empty()
+    }
+}
+
+impl ColorDisplay<StrFormat> for String {
+    fn color_fmt(
+        &self,
+        sink: &mut impl Write,
+        format: &StrFormat,
+    ) -> std::fmt::Result {
+        self.as_str().color_fmt(sink, format)
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &StrFormat,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        self.as_str().color_fmt_in(sink, format, ctx)
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct StrFormat {
     pub indent: u16,
-    pub delimiter: &'static str,
-    pub prefix: &'static str,
+    pub delimiter: Text,
+    pub delimiter_style_desc: Option<StyleDesc>,
+    pub prefix: Text,
+    pub prefix_style_desc: Option<StyleDesc>,
+    pub suffix: Text,
+    pub suffix_style_desc: Option<StyleDesc>,
+    /// How to escape special characters.  When escaping, occurrences of
+    /// `delimiter` in the text are escaped with a backslash as well.
+    pub escape: Escape,
+    /// The maximum number of grapheme clusters of the text to write.
+    /// Longer text is cut short and followed by `ellipsis`.
+    pub max_len: Option<usize>,
+    pub ellipsis: Text,
     pub style_desc: Option<StyleDesc>,
+    /// The style of escape sequences and of the ellipsis.
+    pub escape_style_desc: Option<StyleDesc>,
+    /// Rules to highlight parts of the text with.  Where the matches of
+    /// several rules overlap, the rule that comes first wins.
+    pub highlights: Highlights,
+    /// How to break text that is too long to fit on a line.
+    pub wrap: Option<Wrap>,
 }
 
-impl Format for StrFormat {
-    fn colored(indent: u16) -> Self {
+impl_leaf_format!(StrFormat);
+
+impl StrFormat {
+    pub const fn colored(indent: u16) -> Self {
         Self {
             indent,
-            delimiter: "",
-            prefix: "",
-            style_desc: Some(StyleDesc {
-                color: Color::Green,
-                bold: false,
-                italic: false,
-                underline: false,
-                dimmed: false,
-            }),
+            delimiter: Text::Borrowed(""),
+            delimiter_style_desc: Some(StyleDesc::PUNCTUATION),
+            prefix: Text::Borrowed(""),
+            prefix_style_desc: Some(StyleDesc::PUNCTUATION),
+            suffix: Text::Borrowed(""),
+            suffix_style_desc: Some(StyleDesc::PUNCTUATION),
+            escape: Escape::None,
+            max_len: None,
+            ellipsis: Text::Borrowed("…"),
+            style_desc: Some(StyleDesc::fg(Color::Green)),
+            escape_style_desc: Some(StyleDesc::fg(Color::Yellow).bold()),
+            highlights: Highlights::Borrowed(&[]),
+            wrap: None,
         }
     }
 
-    fn monochrome(indent: u16) -> Self {
+    pub const fn monochrome(indent: u16) -> Self {
         Self {
             indent,
-            delimiter: "",
-            prefix: "",
+            delimiter: Text::Borrowed(""),
+            delimiter_style_desc: None,
+            prefix: Text::Borrowed(""),
+            prefix_style_desc: None,
+            suffix: Text::Borrowed(""),
+            suffix_style_desc: None,
+            escape: Escape::None,
+            max_len: None,
+            ellipsis: Text::Borrowed("…"),
             style_desc: None,
+            escape_style_desc: None,
+            highlights: Highlights::Borrowed(&[]),
+            wrap: None,
         }
     }
 }
 
+impl Format for StrFormat {
+    fn colored(indent: u16) -> Self {
+        Self::colored(indent)
+    }
+
+    fn monochrome(indent: u16) -> Self {
+        Self::monochrome(indent)
+    }
+}
+
+impl Default for StrFormat {
+    fn default() -> Self {
+        Self::colored(0)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{Color, ColorDisplay, StyleDesc};
+    use crate::highlight::Highlight;
+    use crate::wrap::WrapMode;
     use super::*;
 
     #[test]
@@ -64,18 +271,185 @@ mod test {
         let mut sink = String::with_capacity(1024);
         s.color_fmt(&mut sink, &StrFormat {
             indent: 0,
-            delimiter: "",
-            prefix: "",
+            delimiter: "".into(),
+            delimiter_style_desc: None,
+            prefix: "".into(),
+            prefix_style_desc: None,
+            suffix: "".into(),
+            suffix_style_desc: None,
+            escape: Escape::None,
+            max_len: None,
+            ellipsis: "…".into(),
             style_desc: Some(StyleDesc {
-                color: Color::Red,
+                color: Some(Color::Red),
+                background: None,
                 bold: true,
                 italic: false,
                 underline: true,
                 dimmed: false,
+                blink: false,
+                reverse: false,
+                hidden: false,
+                strikethrough: false,
             }),
+            escape_style_desc: None,
+            highlights: Highlights::Borrowed(&[]),
+            wrap: None,
         })?;
         let expected = format!("{}", Color::Red.bold().underline().paint(s));
         assert_eq!(sink, expected);
         Ok(())
     }
+
+    #[test]
+    fn stray_escapes_color_fmt() -> std::fmt::Result {
+        for text in ["user\x1bé input continues", "ends with esc\x1b", "x\x1b[12"] {
+            let mut sink = String::with_capacity(1024);
+            text.color_fmt(&mut sink, &StrFormat::monochrome(0))?;
+            assert_eq!(sink, text);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn multi_line_color_fmt() -> std::fmt::Result {
+        let s = "Alice and Bob\nhave a drink.";
+        let mut sink = String::with_capacity(1024);
+        s.color_fmt(&mut sink, &StrFormat::colored(1))?;
+        let green = Color::Green.normal();
+        let expected = format!(
+            "    {}\n    {}",
+            green.paint("Alice and Bob"),
+            green.paint("have a drink."),
+        );
+        assert_eq!(sink, expected);
+
+        let mut sink = String::with_capacity(1024);
+        s.color_fmt_in(&mut sink, &StrFormat::monochrome(1), &mut Context {
+            indent_token: Some("  ".into()),
+            newline_token: Some("\r\n".into()),
+            ..Context::default()
+        })?;
+        assert_eq!(sink, "  Alice and Bob\r\n  have a drink.");
+        Ok(())
+    }
+
+    #[test]
+    fn escaped_color_fmt() -> std::fmt::Result {
+        let s = "say \"hi\"\n\t\\\x1b";
+        for (escape, expected) in [
+            (Escape::Debug, r#""say \"hi\"\n\t\\\u{1b}""#),
+            (Escape::Json, r#""say \"hi\"\n\t\\\u001b""#),
+            (Escape::ControlPictures, r#""say \"hi\"␊␉\\␛""#),
+        ] {
+            let mut sink = String::with_capacity(1024);
+            s.color_fmt(&mut sink, &StrFormat {
+                delimiter: "\"".into(),
+                escape,
+                ..StrFormat::monochrome(0)
+            })?;
+            assert_eq!(sink, expected);
+        }
+
+        let mut sink = String::with_capacity(1024);
+        "a\nb".color_fmt(&mut sink, &StrFormat {
+            escape: Escape::Debug,
+            ..StrFormat::colored(0)
+        })?;
+        let expected = format!(
+            "{}{}{}",
+            Color::Green.paint("a"),
+            Color::Yellow.bold().paint("\\n"),
+            Color::Green.paint("b"),
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_color_fmt() -> std::fmt::Result {
+        // "e\u{301}" is a single grapheme cluster that must not be split.
+        let s = "cafe\u{301}s and more";
+        let mut sink = String::with_capacity(1024);
+        s.color_fmt(&mut sink, &StrFormat {
+            max_len: Some(4),
+            ellipsis: "...".into(),
+            ..StrFormat::monochrome(0)
+        })?;
+        assert_eq!(sink, "cafe\u{301}...");
+
+        let mut sink = String::with_capacity(1024);
+        s.color_fmt(&mut sink, &StrFormat {
+            max_len: Some(100),
+            ..StrFormat::monochrome(0)
+        })?;
+        assert_eq!(sink, s);
+        Ok(())
+    }
+
+    #[test]
+    fn highlighted_color_fmt() -> std::fmt::Result {
+        let hit = |color: Color| StyleDesc {
+            color: Some(color),
+            background: None,
+            bold: true,
+            italic: false,
+            underline: false,
+            dimmed: false,
+            blink: false,
+            reverse: false,
+            hidden: false,
+            strikethrough: false,
+        };
+        let mut sink = String::with_capacity(1024);
+        "an Error, and errors\n".color_fmt(&mut sink, &StrFormat {
+            escape: Escape::Debug,
+            highlights: vec![
+                Highlight::literal("rors", hit(Color::Blue)),
+                Highlight::case_insensitive("error", hit(Color::Red)),
+                Highlight::literal("s\n", hit(Color::Purple)),
+            ].into(),
+            ..StrFormat::colored(0)
+        })?;
+        let expected = format!(
+            "{}{}{}{}{}{}",
+            Color::Green.paint("an "),
+            Color::Red.bold().paint("Error"),
+            Color::Green.paint(", and "),
+            Color::Red.bold().paint("er"),
+            Color::Blue.bold().paint("rors"),
+            // Layered over the style of the escape sequence.
+            Color::Purple.bold().paint("\\n"),
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn wrapped_color_fmt() -> std::fmt::Result {
+        let mut sink = String::with_capacity(1024);
+        let mut ctx = Context { depth: 2, ..Context::default() };
+        "lorem ipsum dolor sit amet".color_fmt_in(&mut sink, &StrFormat {
+            prefix: "key: ".into(),
+            wrap: Some(Wrap { width: 24, mode: WrapMode::Word, marker: "↪ ".into() }),
+            ..StrFormat::monochrome(0)
+        }, &mut ctx)?;
+        let expected = concat!(
+            "    key: lorem ipsum\n",
+            "         ↪ dolor sit\n",
+            "         ↪ amet",
+        );
+        assert_eq!(sink, expected);
+        assert_eq!(ctx.column, 15);
+
+        let mut sink = String::with_capacity(1024);
+        "色は匂へど".color_fmt(&mut sink, &StrFormat {
+            wrap: Some(Wrap { width: 4, mode: WrapMode::Hard, marker: "".into() }),
+            ..StrFormat::colored(0)
+        })?;
+        let green = Color::Green.prefix().to_string();
+        let expected = format!("{green}色は\x1b[0m\n{green}匂へ\x1b[0m\n{green}ど\x1b[0m");
+        assert_eq!(sink, expected);
+        Ok(())
+    }
 }
@@ -0,0 +1,331 @@
+//! Parsing `StyleDesc`s from text, e.g. from command line flags or
+//! environment variables, and writing them back.
+//!
+//! Two grammars are accepted:
+//!
+//! - Words separated by whitespace, like `"bold italic #ff8800 on blue"`:
+//!   attributes, a foreground color, and a background color after `on`.
+//!   Colors are named, like `red`, or written as `fixed(208)`,
+//!   `rgb(255,136,0)` or `#ff8800`.  `plain` is the style without any.
+//! - SGR parameter lists like `"1;38;5;208"`, as used by `LS_COLORS` and
+//!   `GREP_COLORS`.
+//!
+//! `StyleDesc`s are displayed in the first grammar, such that parsing the
+//! displayed text yields the same `StyleDesc`.
+
+use crate::{ansi, Color, StyleDesc};
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// An error from parsing a `StyleDesc`, pointing at the offending token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseStyleError {
+    /// The text that was parsed.
+    pub spec: String,
+    /// The byte range of the offending token in `spec`, which is empty at
+    /// the end of `spec` when a token is missing.
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl ParseStyleError {
+    fn new(spec: &str, span: Range<usize>, message: String) -> Self {
+        Self { spec: spec.to_string(), span, message }
+    }
+}
+
+impl fmt::Display for ParseStyleError {
+    /// The message, followed by `spec` with the span underlined.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let indent = ansi::visible_width(&self.spec[..self.span.start]);
+        let width = ansi::visible_width(&self.spec[self.span.clone()]).max(1);
+        write!(
+            f, "{}\n  {}\n  {}{}",
+            self.message, self.spec, " ".repeat(indent), "^".repeat(width),
+        )
+    }
+}
+
+impl std::error::Error for ParseStyleError {}
+
+impl FromStr for StyleDesc {
+    type Err = ParseStyleError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let is_sgr = !spec.is_empty()
+            && spec.bytes().all(|b| b.is_ascii_digit() || b == b';');
+        if is_sgr {
+            parse_sgr(spec)
+        } else {
+            parse_words(spec)
+        }
+    }
+}
+
+impl fmt::Display for StyleDesc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let attributes = [
+            ("bold", self.bold),
+            ("dimmed", self.dimmed),
+            ("italic", self.italic),
+            ("underline", self.underline),
+            ("blink", self.blink),
+            ("reverse", self.reverse),
+            ("hidden", self.hidden),
+            ("strikethrough", self.strikethrough),
+        ];
+        let mut words: Vec<String> = attributes.iter()
+            .filter(|(_, set)| *set)
+            .map(|(name, _)| name.to_string())
+            .collect();
+        if let Some(color) = self.color {
+            words.push(color_name(color));
+        }
+        if let Some(background) = self.background {
+            words.push(format!("on {}", color_name(background)));
+        }
+        match words.is_empty() {
+            true => f.write_str("plain"),
+            false => f.write_str(&words.join(" ")),
+        }
+    }
+}
+
+const COLORS: [(&str, Color); 8] = [
+    ("black", Color::Black),
+    ("red", Color::Red),
+    ("green", Color::Green),
+    ("yellow", Color::Yellow),
+    ("blue", Color::Blue),
+    ("purple", Color::Purple),
+    ("cyan", Color::Cyan),
+    ("white", Color::White),
+];
+
+fn color_name(color: Color) -> String {
+    match color {
+        Color::Fixed(n) => format!("fixed({n})"),
+        Color::RGB(r, g, b) => format!("#{r:02x}{g:02x}{b:02x}"),
+        color => COLORS.iter()
+            .find(|(_, c)| *c == color)
+            .map_or_else(String::new, |(name, _)| name.to_string()),
+    }
+}
+
+/// `style` with the attribute `name` set, if it is one.
+fn with_attribute(style: StyleDesc, name: &str) -> Option<StyleDesc> {
+    Some(match name {
+        "bold" => style.bold(),
+        "dimmed" | "dim" => style.dimmed(),
+        "italic" => style.italic(),
+        "underline" => style.underline(),
+        "blink" => style.blink(),
+        "reverse" => style.reverse(),
+        "hidden" => style.hidden(),
+        "strikethrough" => style.strikethrough(),
+        _ => return None,
+    })
+}
+
+fn parse_color(word: &str) -> Option<Color> {
+    let word = word.to_ascii_lowercase();
+    if let Some(hex) = word.strip_prefix('#') {
+        let channel = |idx: usize| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok();
+        return match hex.len() {
+            6 => Some(Color::RGB(channel(0)?, channel(2)?, channel(4)?)),
+            _ => None,
+        };
+    }
+    if let Some(n) = word.strip_prefix("fixed(").and_then(|w| w.strip_suffix(')')) {
+        return n.parse().ok().map(Color::Fixed);
+    }
+    if let Some(rgb) = word.strip_prefix("rgb(").and_then(|w| w.strip_suffix(')')) {
+        let channels: Result<Vec<u8>, _> = rgb.split(',').map(str::parse).collect();
+        return match channels.ok()?[..] {
+            [r, g, b] => Some(Color::RGB(r, g, b)),
+            _ => None,
+        };
+    }
+    match word.as_str() {
+        "magenta" => Some(Color::Purple),
+        word => COLORS.iter().find(|(name, _)| *name == word).map(|(_, c)| *c),
+    }
+}
+
+/// The words of `spec`, with their byte ranges.
+fn words(spec: &str) -> Vec<(&str, Range<usize>)> {
+    let mut words = vec![];
+    let mut start = None;
+    for (idx, c) in spec.char_indices().chain([(spec.len(), ' ')]) {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(idx),
+            (Some(s), true) => {
+                words.push((&spec[s..idx], s..idx));
+                start = None;
+            },
+            _ => {},
+        }
+    }
+    words
+}
+
+fn parse_words(spec: &str) -> Result<StyleDesc, ParseStyleError> {
+    let error = |span, message| Err(ParseStyleError::new(spec, span, message));
+    let mut style = StyleDesc::PLAIN;
+    let mut words = words(spec).into_iter();
+    while let Some((word, span)) = words.next() {
+        if word == "plain" {
+            continue;
+        }
+        if word == "on" {
+            let Some((word, span)) = words.next() else {
+                let end = spec.len()..spec.len();
+                return error(end, "expected a background color after `on`".into());
+            };
+            let Some(background) = parse_color(word) else {
+                return error(span, format!("unknown background color `{word}`"));
+            };
+            if style.background.is_some() {
+                return error(span, format!("a second background color `{word}`"));
+            }
+            style.background = Some(background);
+        } else if let Some(color) = parse_color(word) {
+            if style.color.is_some() {
+                return error(span, format!("a second foreground color `{word}`"));
+            }
+            style.color = Some(color);
+        } else if let Some(with) = with_attribute(style, &word.to_ascii_lowercase()) {
+            style = with;
+        } else {
+            return error(span, format!("unknown color or attribute `{word}`"));
+        }
+    }
+    Ok(style)
+}
+
+fn parse_sgr(spec: &str) -> Result<StyleDesc, ParseStyleError> {
+    let error = |span, message| Err(ParseStyleError::new(spec, span, message));
+    // The parameters with their byte ranges; empty parameters mean 0.
+    let mut params = vec![];
+    let mut start = 0;
+    for param in spec.split(';') {
+        let span = start..start + param.len();
+        let value = match param {
+            "" => Ok(0),
+            param => param.parse::<u8>(),
+        };
+        let Ok(value) = value else {
+            return error(span, format!("SGR parameter `{param}` is out of range"));
+        };
+        params.push((value, span));
+        start += param.len() + 1;
+    }
+    let mut style = StyleDesc::PLAIN;
+    let mut params = params.into_iter();
+    while let Some((param, span)) = params.next() {
+        match param {
+            0 => style = StyleDesc::PLAIN,
+            1 => style.bold = true,
+            2 => style.dimmed = true,
+            3 => style.italic = true,
+            4 => style.underline = true,
+            5 => style.blink = true,
+            7 => style.reverse = true,
+            8 => style.hidden = true,
+            9 => style.strikethrough = true,
+            30..=37 => style.color = Some(COLORS[param as usize - 30].1),
+            39 => style.color = None,
+            40..=47 => style.background = Some(COLORS[param as usize - 40].1),
+            49 => style.background = None,
+            // The bright colors are the fixed colors 8 to 15.
+            90..=97 => style.color = Some(Color::Fixed(param - 90 + 8)),
+            100..=107 => style.background = Some(Color::Fixed(param - 100 + 8)),
+            38 | 48 => {
+                let mut next = |what: &str| params.next().ok_or_else(|| {
+                    let end = spec.len()..spec.len();
+                    let message = format!("expected {what} after `{param}`");
+                    ParseStyleError::new(spec, end, message)
+                });
+                let color = match next("a color mode")? {
+                    (5, _) => Color::Fixed(next("a color number")?.0),
+                    (2, _) => {
+                        let (r, _) = next("a red value")?;
+                        let (g, _) = next("a green value")?;
+                        let (b, _) = next("a blue value")?;
+                        Color::RGB(r, g, b)
+                    },
+                    (mode, span) => {
+                        let message = format!("unknown color mode `{mode}`");
+                        return error(span, message + ", expected 2 or 5");
+                    },
+                };
+                match param {
+                    38 => style.color = Some(color),
+                    _ => style.background = Some(color),
+                }
+            },
+            param => return error(span, format!("unsupported SGR parameter `{param}`")),
+        }
+    }
+    Ok(style)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_words() {
+        let style: StyleDesc = "bold italic #ff8800 on blue".parse().unwrap();
+        let expected = StyleDesc::fg(Color::RGB(255, 136, 0)).on(Color::Blue);
+        assert_eq!(style, expected.bold().italic());
+        let style = "  Dim  MAGENTA ".parse();
+        assert_eq!(style, Ok(StyleDesc::fg(Color::Purple).dimmed()));
+        assert_eq!("on fixed(208)".parse(), Ok(StyleDesc::bg(Color::Fixed(208))));
+        assert_eq!("".parse(), Ok(StyleDesc::PLAIN));
+    }
+
+    #[test]
+    fn parse_sgr() {
+        let style: StyleDesc = "1;38;5;208".parse().unwrap();
+        assert_eq!(style, StyleDesc::fg(Color::Fixed(208)).bold());
+        let style: StyleDesc = "4;;31;48;2;1;2;3".parse().unwrap();
+        assert_eq!(style, StyleDesc::fg(Color::Red).on(Color::RGB(1, 2, 3)));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let styles = [
+            StyleDesc::PLAIN,
+            StyleDesc::fg(Color::Cyan).underline().strikethrough(),
+            StyleDesc::bg(Color::RGB(0, 10, 255)).reverse().hidden(),
+            StyleDesc::fg(Color::Fixed(7)).on(Color::White).blink().dimmed(),
+        ];
+        for style in styles {
+            assert_eq!(style.to_string().parse(), Ok(style), "{style}");
+        }
+        assert_eq!(styles[3].to_string(), "dimmed blink fixed(7) on white");
+    }
+
+    #[test]
+    fn errors() {
+        let err = "bold rde on blue".parse::<StyleDesc>().unwrap_err();
+        assert_eq!(err.span, 5..8);
+        assert_eq!(
+            err.to_string(),
+            "unknown color or attribute `rde`\n  bold rde on blue\n       ^^^",
+        );
+        let err = "red on".parse::<StyleDesc>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected a background color after `on`\n  red on\n        ^",
+        );
+        let err = "1;38;7;1".parse::<StyleDesc>().unwrap_err();
+        assert_eq!(err.span, 5..6);
+        assert_eq!(err.message, "unknown color mode `7`, expected 2 or 5");
+        let err = "1;22".parse::<StyleDesc>().unwrap_err();
+        assert_eq!(err.span, 2..4);
+    }
+}
@@ -0,0 +1,590 @@
+//! `ColorDisplay` support for rendering slices of records as a table.
+//!
+//! A `TableFormat<R>` describes the columns of the table. Each `Column<R>`
+//! knows how to extract a value from a record of type `R` and how to format
+//! it.  Cells are rendered first, then measured while ignoring any escape
+//! sequences, so that the columns line up regardless of styling.  A cell may
+//! span multiple lines, which makes its row as tall as its tallest cell.  The
+//! style overlays of enclosing collections apply to the cells and borders
+//! alike.
+
+use crate::{ansi, Color, ColorDisplay, Context, Format, StyleDesc, Text};
+use crate::indent::IndentWriter;
+use crate::layout::{elide, Entry, Limit, Truncation};
+use std::fmt::Write;
+use std::rc::Rc;
+
+impl<R> ColorDisplay<TableFormat<R>> for [R] {
+    fn color_fmt(
+        &self,
+        sink: &mut impl Write,
+        format: &TableFormat<R>,
+    ) -> std::fmt::Result {
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &TableFormat<R>,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        if format.columns.is_empty() {
+            return Ok(());
+        }
+        // Cells are laid out by the table, so they aren't indented.
+        let cell_ctx = Context {
+            depth: ctx.depth + 1,
+            indent_unit: 0,
+            column: 0,
+            guides: vec![],
+            ..ctx.clone()
+        };
+        let rows: Vec<Entry<Vec<Vec<String>>>> = elide(self.iter(), format.truncation.limit)
+            .map(|entry| match entry {
+                Entry::Item(record) => format.columns.iter()
+                    .map(|column| Ok(cell_lines(&column.render(record, &cell_ctx)?)))
+                    .collect::<Result<_, _>>()
+                    .map(Entry::Item),
+                Entry::Elided(count) => Ok(Entry::Elided(count)),
+            })
+            .collect::<Result<_, _>>()?;
+        let header_style = ctx.leaf_style(format.header_style);
+        let headers: Vec<Vec<String>> = format.columns.iter()
+            .map(|column| header_style.paint(column.header.as_ref()).to_string())
+            .map(|header| cell_lines(&header))
+            .collect();
+        let cells = rows.iter().filter_map(|row| match row {
+            Entry::Item(cells) => Some(cells.as_slice()),
+            Entry::Elided(_) => None,
+        });
+        let widths = format.column_widths(&headers, cells);
+        let border_style = ctx.leaf_style(format.border_style);
+        let chars = format.borders.chars();
+        let mut lines: Vec<String> = vec![];
+        let rule = |[left, mid, right]: [char; 3]| {
+            let rule = widths.iter()
+                .map(|&width| chars.horizontal.to_string().repeat(width + 2))
+                .collect::<Vec<_>>()
+                .join(&mid.to_string());
+            format!("{}", border_style.paint(format!("{left}{rule}{right}")))
+        };
+        if format.borders != Borders::None {
+            lines.push(rule(chars.top));
+        }
+        if format.header {
+            lines.extend(format.row(&headers, &widths, None, ctx));
+            if format.borders != Borders::None {
+                lines.push(rule(chars.middle));
+            }
+        }
+        for (idx, row) in rows.iter().enumerate() {
+            match row {
+                Entry::Item(cells) => {
+                    let zebra = if idx % 2 == 1 { format.zebra_style } else { None };
+                    lines.extend(format.row(cells, &widths, zebra, ctx));
+                },
+                Entry::Elided(count) => lines.push(format.elision(*count, &widths, ctx)),
+            }
+        }
+        if format.borders != Borders::None {
+            lines.push(rule(chars.bottom));
+        }
+        for (idx, line) in lines.iter().enumerate() {
+            if idx > 0 {
+                self.write_newlines_in(sink, 1, format, ctx)?;
+            }
+            let indent = ctx.indentation(format.indent);
+            self.write_indentation_in(sink, indent, format, ctx)?;
+            ctx.write(sink, line)?;
+        }
+        Ok(())
+    }
+}
+
+impl<R> ColorDisplay<TableFormat<R>> for Vec<R> {
+    fn color_fmt(
+        &self,
+        sink: &mut impl Write,
+        format: &TableFormat<R>,
+    ) -> std::fmt::Result {
+        self.as_slice().color_fmt(sink, format)
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &TableFormat<R>,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        self.as_slice().color_fmt_in(sink, format, ctx)
+    }
+}
+
+/// Describes a single column of a table of records of type `R`.
+pub struct Column<R> {
+    pub header: Text,
+    pub alignment: Alignment,
+    render: Rc<RenderCell<R>>,
+}
+
+type RenderCell<R> = dyn Fn(&R, &mut String, &mut Context) -> std::fmt::Result;
+
+impl<R> Column<R> {
+    /// Create a column that formats the value returned by `accessor`
+    /// using `format`.
+    pub fn new<T, F>(
+        header: impl Into<Text>,
+        format: F,
+        accessor: impl Fn(&R) -> T + 'static,
+    ) -> Self
+    where
+        T: ColorDisplay<F>,
+        F: 'static,
+    {
+        Self::custom(header, move |record, sink, ctx| {
+            accessor(record).color_fmt_in(sink, &format, ctx)
+        })
+    }
+
+    /// Create a column whose cells are written by `render`, in the context
+    /// of the cell.  This is useful e.g. for values borrowed from the record.
+    pub fn custom(
+        header: impl Into<Text>,
+        render: impl Fn(&R, &mut String, &mut Context) -> std::fmt::Result + 'static,
+    ) -> Self {
+        Self {
+            header: header.into(),
+            alignment: Alignment::Left,
+            render: Rc::new(render),
+        }
+    }
+
+    pub fn align(self, alignment: Alignment) -> Self {
+        Self { alignment, ..self }
+    }
+
+    fn render(&self, record: &R, ctx: &Context) -> Result<String, std::fmt::Error> {
+        let mut cell = String::new();
+        (self.render)(record, &mut cell, &mut ctx.clone())?;
+        Ok(cell)
+    }
+}
+
+impl<R> Clone for Column<R> {
+    fn clone(&self) -> Self {
+        Self {
+            header: self.header.clone(),
+            alignment: self.alignment,
+            render: self.render.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    Right,
+    Center,
+}
+
+impl Alignment {
+    /// Pad `text` with spaces to a visible width of `width`.
+    pub fn pad(self, text: &str, width: usize) -> String {
+        let padding = width.saturating_sub(ansi::visible_width(text));
+        let (left, right) = match self {
+            Self::Left => (0, padding),
+            Self::Right => (padding, 0),
+            Self::Center => (padding / 2, padding - padding / 2),
+        };
+        format!("{}{text}{}", " ".repeat(left), " ".repeat(right))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Borders {
+    /// Columns are separated by whitespace only.
+    None,
+    /// Borders drawn with `+`, `-` and `|`.
+    Ascii,
+    /// Borders drawn with Unicode box-drawing characters.
+    Unicode,
+}
+
+struct BorderChars {
+    horizontal: char,
+    vertical: char,
+    top: [char; 3],
+    middle: [char; 3],
+    bottom: [char; 3],
+}
+
+impl Borders {
+    fn chars(self) -> BorderChars {
+        match self {
+            Self::None | Self::Ascii => BorderChars {
+                horizontal: '-',
+                vertical: '|',
+                top: ['+', '+', '+'],
+                middle: ['+', '+', '+'],
+                bottom: ['+', '+', '+'],
+            },
+            Self::Unicode => BorderChars {
+                horizontal: '─',
+                vertical: '│',
+                top: ['┌', '┬', '┐'],
+                middle: ['├', '┼', '┤'],
+                bottom: ['└', '┴', '┘'],
+            },
+        }
+    }
+}
+
+pub struct TableFormat<R> {
+    pub indent: u16,
+    pub columns: Vec<Column<R>>,
+    /// Whether to write a row with the column headers.
+    pub header: bool,
+    pub borders: Borders,
+    /// The rows to write.  The omitted rows are replaced by a row with an
+    /// elision marker.
+    pub truncation: Truncation,
+    /// The maximum visible width of a row, excluding indentation.
+    /// When the columns don't fit, the widest ones are truncated first.
+    pub max_width: Option<usize>,
+    /// The text that marks a truncated cell.
+    pub ellipsis: Text,
+    pub header_style: Option<StyleDesc>,
+    pub border_style: Option<StyleDesc>,
+    /// The style layered over every other row, if any.
+    pub zebra_style: Option<StyleDesc>,
+}
+
+impl<R> TableFormat<R> {
+    /// Add a column to the table.
+    pub fn column(mut self, column: Column<R>) -> Self {
+        self.columns.push(column);
+        self
+    }
+
+    /// Write only the first `max_items` rows.
+    pub fn max_items(self, max_items: usize) -> Self {
+        self.limit(Limit::Head(max_items))
+    }
+
+    /// Write only the first `head` and the last `tail` rows.
+    pub fn head_tail(self, head: usize, tail: usize) -> Self {
+        self.limit(Limit::HeadTail { head, tail })
+    }
+
+    fn limit(self, limit: Limit) -> Self {
+        let truncation = Truncation { limit: Some(limit), ..self.truncation };
+        Self { truncation, ..self }
+    }
+
+    /// Compute the width of each column, i.e. the visible width of the widest
+    /// line of its cells and header, and shrink the widest ones until
+    /// `self.max_width` fits.
+    fn column_widths<'c>(
+        &self,
+        headers: &'c [Vec<String>],
+        cells: impl Iterator<Item = &'c [Vec<String>]>,
+    ) -> Vec<usize> {
+        let mut widths = vec![0; self.columns.len()];
+        for row in std::iter::once(headers).chain(cells) {
+            for (width, lines) in widths.iter_mut().zip(row) {
+                for line in lines {
+                    *width = (*width).max(ansi::visible_width(line));
+                }
+            }
+        }
+        let Some(max_width) = self.max_width else { return widths };
+        let overhead = match self.borders {
+            Borders::None => 2 * widths.len().saturating_sub(1),
+            Borders::Ascii | Borders::Unicode => 3 * widths.len() + 1,
+        };
+        while widths.iter().sum::<usize>() + overhead > max_width {
+            let widest = widths.iter_mut().max().filter(|width| **width > 1);
+            match widest {
+                Some(width) => *width -= 1,
+                None => break,
+            }
+        }
+        widths
+    }
+
+    /// The lines of a row, which is as tall as its tallest cell.
+    fn row(
+        &self,
+        cells: &[Vec<String>],
+        widths: &[usize],
+        zebra: Option<StyleDesc>,
+        ctx: &Context,
+    ) -> Vec<String> {
+        let height = cells.iter().map(Vec::len).max().unwrap_or(0).max(1);
+        (0..height).map(|idx| {
+            let cells = self.columns.iter().zip(cells).zip(widths)
+                .map(|((column, lines), &width)| {
+                    let line = lines.get(idx).map_or("", String::as_str);
+                    let line = ansi::truncate(line, width, &self.ellipsis);
+                    let line = column.alignment.pad(&line, width);
+                    match zebra {
+                        Some(_) => ansi::layer(ctx.leaf_style(zebra), &line),
+                        None => line,
+                    }
+                });
+            if self.borders == Borders::None {
+                return cells.collect::<Vec<_>>().join("  ");
+            }
+            let style = ctx.leaf_style(self.border_style);
+            let vertical = style.paint(self.borders.chars().vertical.to_string());
+            let mut row = String::new();
+            for cell in cells {
+                let _ = write!(row, "{vertical} {cell} ");
+            }
+            let _ = write!(row, "{vertical}");
+            row
+        }).collect()
+    }
+
+    /// The row that replaces `count` omitted rows, spanning all columns.
+    fn elision(&self, count: usize, widths: &[usize], ctx: &Context) -> String {
+        let style = ctx.leaf_style(self.truncation.style_desc);
+        let marker = style.paint(format!("… {count} more")).to_string();
+        if self.borders == Borders::None {
+            return marker;
+        }
+        let separators = widths.len().saturating_sub(1);
+        let width = widths.iter().sum::<usize>() + 3 * separators;
+        let marker = ansi::truncate(&marker, width, &self.ellipsis);
+        let marker = Alignment::Left.pad(&marker, width);
+        let style = ctx.leaf_style(self.border_style);
+        let vertical = style.paint(self.borders.chars().vertical.to_string());
+        format!("{vertical} {marker} {vertical}")
+    }
+}
+
+/// The lines of `cell`, each with the styles that are active at its start
+/// re-opened, and reset at its end.
+fn cell_lines(cell: &str) -> Vec<String> {
+    if !cell.contains('\n') {
+        return vec![cell.to_string()];
+    }
+    let mut text = String::with_capacity(cell.len() + 16);
+    let mut writer = IndentWriter::new(&mut text, "");
+    let _ = writer.write_str(cell).and_then(|()| writer.finish());
+    text.split('\n').map(str::to_string).collect()
+}
+
+impl<R> Clone for TableFormat<R> {
+    fn clone(&self) -> Self {
+        Self {
+            columns: self.columns.clone(),
+            ellipsis: self.ellipsis.clone(),
+            ..*self
+        }
+    }
+}
+
+impl<R> Format for TableFormat<R> {
+    fn colored(indent: u16) -> Self {
+        Self {
+            indent,
+            columns: vec![],
+            header: true,
+            borders: Borders::Unicode,
+            truncation: Truncation::colored(indent),
+            max_width: None,
+            ellipsis: "…".into(),
+            header_style: Some(StyleDesc::fg(Color::White).bold().underline()),
+            border_style: Some(StyleDesc::PUNCTUATION),
+            zebra_style: Some(StyleDesc::fg(Color::White).dimmed()),
+        }
+    }
+
+    fn monochrome(indent: u16) -> Self {
+        Self {
+            indent,
+            columns: vec![],
+            header: true,
+            borders: Borders::Ascii,
+            truncation: Truncation::monochrome(indent),
+            max_width: None,
+            ellipsis: "...".into(),
+            header_style: None,
+            border_style: None,
+            zebra_style: None,
+        }
+    }
+}
+
+impl<R> Default for TableFormat<R> {
+    fn default() -> Self {
+        Self::colored(0)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        Cascade, Color, ColorDisplay, StyleOverlay,
+        primitive::U32Format,
+        str::StrFormat,
+    };
+    use super::*;
+
+    struct Person {
+        name: String,
+        age: u32,
+    }
+
+    fn people() -> Vec<Person> {
+        vec![
+            Person { name: "Alice".to_string(), age: 30 },
+            Person { name: "Bob".to_string(), age: 4 },
+        ]
+    }
+
+    #[test]
+    fn monochrome_color_fmt() -> std::fmt::Result {
+        let format = TableFormat::monochrome(1)
+            .column(Column::new("name", StrFormat::monochrome(0), |p: &Person| {
+                p.name.clone()
+            }))
+            .column(Column::new("age", U32Format::monochrome(0), |p: &Person| {
+                p.age
+            }).align(Alignment::Right));
+        let mut sink = String::with_capacity(1024);
+        people().color_fmt(&mut sink, &format)?;
+        let expected = concat!(
+            "    +-------+-----+\n",
+            "    | name  | age |\n",
+            "    +-------+-----+\n",
+            "    | Alice |  30 |\n",
+            "    | Bob   |   4 |\n",
+            "    +-------+-----+",
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn colored_truncated_color_fmt() -> std::fmt::Result {
+        let format = TableFormat {
+            borders: Borders::None,
+            max_width: Some(9),
+            ..TableFormat::colored(0)
+        }
+            .column(Column::custom("name", |p: &Person, sink, ctx| {
+                p.name.as_str().color_fmt_in(sink, &StrFormat::colored(0), ctx)
+            }))
+            .column(Column::new("age", U32Format::monochrome(0), |p: &Person| {
+                p.age
+            }));
+        let mut sink = String::with_capacity(1024);
+        people().color_fmt(&mut sink, &format)?;
+        let header = Color::White.bold().underline();
+        let zebra = Color::White.dimmed().prefix();
+        // The zebra style is re-applied after the reset of the name cell.
+        let expected = format!(
+            "{}  {}\n{}  30 \n{zebra}{}{zebra} \x1b[0m  {zebra}4  \x1b[0m",
+            header.paint("name"),
+            header.paint("age"),
+            Color::Green.paint("Ali…"),
+            Color::Green.paint("Bob"),
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn style_overlay_color_fmt() -> std::fmt::Result {
+        let format = TableFormat { borders: Borders::Ascii, ..TableFormat::monochrome(0) }
+            .column(Column::new("age", U32Format::monochrome(0), |p: &Person| {
+                p.age
+            }));
+        let mut sink = String::with_capacity(1024);
+        let mut ctx = Context {
+            style_overlay: StyleOverlay { dimmed: Cascade::Set(true), ..StyleOverlay::INHERIT },
+            ..Context::default()
+        };
+        people()[..1].color_fmt_in(&mut sink, &format, &mut ctx)?;
+        let dimmed = |text: &str| ansi_term::Style::new().dimmed().paint(text).to_string();
+        let rule = dimmed("+-----+");
+        let row = |cell: &str| format!("{} {cell} {}", dimmed("|"), dimmed("|"));
+        let expected = format!(
+            "{rule}\n{}\n{rule}\n{}\n{rule}",
+            row(&dimmed("age")),
+            row(&format!("{} ", dimmed("30"))),
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn multi_line_color_fmt() -> std::fmt::Result {
+        let format = TableFormat::monochrome(0)
+            .column(Column::new("id", StrFormat::monochrome(0), |p: &Person| {
+                format!("{}\nx", p.age)
+            }))
+            .column(Column::new("name", StrFormat::monochrome(0), |p: &Person| {
+                p.name.clone()
+            }));
+        let mut sink = String::with_capacity(1024);
+        people()[..1].color_fmt(&mut sink, &format)?;
+        let expected = concat!(
+            "+----+-------+\n",
+            "| id | name  |\n",
+            "+----+-------+\n",
+            "| 30 | Alice |\n",
+            "| x  |       |\n",
+            "+----+-------+",
+        );
+        assert_eq!(sink, expected);
+
+        // Styles don't run across the borders.
+        let mut sink = String::with_capacity(1024);
+        let format = TableFormat { header: false, ..TableFormat::monochrome(0) }
+            .column(Column::new("", StrFormat::colored(0), |_: &Person| "a\nbc"));
+        people()[..1].color_fmt(&mut sink, &format)?;
+        let green = Color::Green.prefix();
+        let expected = format!(
+            "+----+\n| {green}a\x1b[0m  |\n| {green}bc\x1b[0m |\n+----+",
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn no_columns_color_fmt() -> std::fmt::Result {
+        let mut sink = String::with_capacity(1024);
+        people().color_fmt(&mut sink, &TableFormat::monochrome(0))?;
+        assert_eq!(sink, "");
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_rows_color_fmt() -> std::fmt::Result {
+        let format = TableFormat::monochrome(0)
+            .column(Column::new("name", StrFormat::monochrome(0), |p: &Person| {
+                p.name.clone()
+            }))
+            .column(Column::new("age", U32Format::monochrome(0), |p: &Person| {
+                p.age
+            }))
+            .max_items(1);
+        let mut sink = String::with_capacity(1024);
+        people().color_fmt(&mut sink, &format)?;
+        let expected = concat!(
+            "+-------+-----+\n",
+            "| name  | age |\n",
+            "+-------+-----+\n",
+            "| Alice | 30  |\n",
+            "| … 1 more    |\n",
+            "+-------+-----+",
+        );
+        assert_eq!(sink, expected);
+        Ok(())
+    }
+}
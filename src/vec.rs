@@ -1,6 +1,8 @@
-//!
+//! `ColorDisplay` support for `Vec<T>`.
 
 use super::*;
+use crate::layout::{impl_collection_builders, Layout, Truncation};
+use crate::slice::SliceFormat;
 
 impl<TF, T> ColorDisplay<VecFormat<TF>> for Vec<T>
 where
@@ -12,27 +14,81 @@ where
         sink: &mut impl Write,
         format: &VecFormat<TF>,
     ) -> std::fmt::Result {
-        self.as_slice().color_fmt(sink, &crate::slice::SliceFormat {
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
+
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &VecFormat<TF>,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        self.as_slice().color_fmt_in(sink, &format.to_slice_format(), ctx)
+    }
+}
+
+#[derive(Clone)]
+pub struct VecFormat<TF> {
+    pub prefix_newlines: u16,
+    pub intersperse_newlines: u16,
+    pub suffix_newlines: u16,
+    pub layout: Layout,
+    pub truncation: Truncation,
+    /// Layered over the styles of the descendants, see `StyleOverlay`.
+    pub style_overlay: StyleOverlay,
+    pub item_format: TF,
+}
+
+impl<TF: Format> Format for VecFormat<TF> {
+    fn colored(indent: u16) -> Self {
+        SliceFormat::colored(indent).into()
+    }
+
+    fn monochrome(indent: u16) -> Self {
+        SliceFormat::monochrome(indent).into()
+    }
+}
+
+impl<TF: Format> Default for VecFormat<TF> {
+    fn default() -> Self {
+        Self::colored(0)
+    }
+}
+
+impl<TF> From<SliceFormat<TF>> for VecFormat<TF> {
+    fn from(format: SliceFormat<TF>) -> Self {
+        Self {
             prefix_newlines: format.prefix_newlines,
             intersperse_newlines: format.intersperse_newlines,
             suffix_newlines: format.suffix_newlines,
-            item_format: format.item_format.clone(),
-        })
+            layout: format.layout,
+            truncation: format.truncation,
+            style_overlay: format.style_overlay,
+            item_format: format.item_format,
+        }
     }
 }
 
-#[derive(Clone, Copy)]
-pub struct VecFormat<TF> {
-    pub(crate) prefix_newlines: u16,
-    pub(crate) intersperse_newlines: u16,
-    pub(crate) suffix_newlines: u16,
-    pub(crate) item_format: TF,
+impl_collection_builders!(impl<TF> VecFormat<TF>);
+
+impl<TF: Clone> VecFormat<TF> {
+    pub(crate) fn to_slice_format(&self) -> SliceFormat<TF> {
+        SliceFormat {
+            prefix_newlines: self.prefix_newlines,
+            intersperse_newlines: self.intersperse_newlines,
+            suffix_newlines: self.suffix_newlines,
+            layout: self.layout.clone(),
+            truncation: self.truncation,
+            style_overlay: self.style_overlay,
+            item_format: self.item_format.clone(),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{primitive::U8Format, Color, ColorDisplay, StyleDesc};
-    use super::VecFormat;
+    use crate::{primitive::U8Format, Color, ColorDisplay, StyleDesc, StyleOverlay};
+    use super::{Layout, Truncation, VecFormat};
 
     #[test]
     fn color_fmt() -> std::fmt::Result {
@@ -42,15 +98,28 @@ mod test {
             prefix_newlines: 1,
             intersperse_newlines: 1,
             suffix_newlines: 1,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(0),
+            style_overlay: StyleOverlay::INHERIT,
             item_format: U8Format {
                 indent: 0,
-                prefix: "",
+                prefix: "".into(),
+                prefix_style_desc: None,
+                delimiter: "".into(),
+                delimiter_style_desc: None,
+                suffix: "".into(),
+                suffix_style_desc: None,
                 style_desc: Some(StyleDesc {
-                    color: Color::Purple,
+                    color: Some(Color::Purple),
+                    background: None,
                     bold: false,
                     italic: false,
                     underline: false,
                     dimmed: false,
+                    blink: false,
+                    reverse: false,
+                    hidden: false,
+                    strikethrough: false,
                 })
             }
         })?;
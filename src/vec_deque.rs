@@ -1,6 +1,10 @@
-//!
+//! `ColorDisplay` support for `VecDeque<T>`.
 
 use super::*;
+use crate::layout::{
+    elide, impl_collection_builders, write_items, CollectionFormat, Entry,
+    Layout, Truncation,
+};
 use std::collections::VecDeque;
 
 impl<TF, T> ColorDisplay<VecDequeFormat<TF>> for VecDeque<T>
@@ -12,37 +16,77 @@ where
         sink: &mut impl Write,
         format: &VecDequeFormat<TF>,
     ) -> std::fmt::Result {
-        self.write_newlines(sink, format.prefix_newlines, &format)?;
-        let (front, back) = self.as_slices();
-        for (idx, item) in front.iter().enumerate() {
-            if idx > 0 {
-                self.write_newlines(sink, format.intersperse_newlines, &format)?;
-            }
-            item.color_fmt(sink, &format.item_format)?;
-        }
-        write!(sink, "\n")?;
-        for _ in 0..format.front_back_separator_count {
-            write!(sink, "{}", format.front_back_separator_token)?;
-        }
-        write!(sink, "\n")?;
-        for (idx, item) in back.iter().enumerate() {
-            if idx > 0 {
-                self.write_newlines(sink, format.intersperse_newlines, &format)?;
+        self.color_fmt_in(sink, format, &mut Context::default())
+    }
+
+    /// In a `Layout::Tree` or `Layout::Inline`, or when `ctx` is at its
+    /// maximum depth, the front and back halves are written as a single
+    /// sequence, without the separator line between them.
+    fn color_fmt_in(
+        &self,
+        sink: &mut impl Write,
+        format: &VecDequeFormat<TF>,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        self.write_newlines_in(sink, format.prefix_newlines, format, ctx)?;
+        if matches!(format.layout, Layout::Tree(_) | Layout::Inline(_)) || ctx.max_depth_reached() {
+            write_items(
+                self, sink, format, ctx,
+                elide(self.iter(), format.truncation.limit),
+                |sink, ctx, item| item.color_fmt_in(sink, &format.item_format, ctx),
+            )?;
+        } else {
+            // Truncate the deque as a whole, then split it into its halves.
+            let front_len = self.as_slices().0.len();
+            let mut front = vec![];
+            let mut back = vec![];
+            let indexed = self.iter().enumerate();
+            for entry in elide(indexed, format.truncation.limit) {
+                match entry {
+                    Entry::Item((idx, item)) if idx >= front_len => {
+                        back.push(Entry::Item(item));
+                    },
+                    Entry::Item((_, item)) => front.push(Entry::Item(item)),
+                    Entry::Elided(count) if back.is_empty() => {
+                        front.push(Entry::Elided(count));
+                    },
+                    Entry::Elided(count) => back.push(Entry::Elided(count)),
+                }
             }
-            item.color_fmt(sink, &format.item_format)?;
+            write_items(
+                self, sink, format, ctx,
+                front,
+                |sink, ctx, item| item.color_fmt_in(sink, &format.item_format, ctx),
+            )?;
+            let separator = format.front_back_separator_token
+                .repeat(format.front_back_separator_count as usize);
+            let separator = ctx.paint(&separator, format.front_back_separator_style_desc);
+            self.write_newlines_in(sink, 1, format, ctx)?;
+            ctx.write(sink, &separator)?;
+            self.write_newlines_in(sink, 1, format, ctx)?;
+            write_items(
+                self, sink, format, ctx,
+                back,
+                |sink, ctx, item| item.color_fmt_in(sink, &format.item_format, ctx),
+            )?;
         }
-        self.write_newlines(sink, format.suffix_newlines, &format)?;
+        self.write_newlines_in(sink, format.suffix_newlines, format, ctx)?;
         Ok(())
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct VecDequeFormat<TF> {
     pub prefix_newlines: u16,
     pub intersperse_newlines: u16,
     pub suffix_newlines: u16,
-    pub front_back_separator_token: &'static str,
+    pub front_back_separator_token: Text,
+    pub front_back_separator_style_desc: Option<StyleDesc>,
     pub front_back_separator_count: u16,
+    pub layout: Layout,
+    pub truncation: Truncation,
+    /// Layered over the styles of the descendants, see `StyleOverlay`.
+    pub style_overlay: StyleOverlay,
     pub item_format: TF,
 }
 
@@ -52,8 +96,12 @@ impl<TF: Format> Format for VecDequeFormat<TF> {
             prefix_newlines: 0,
             intersperse_newlines: 1,
             suffix_newlines: 0,
-            front_back_separator_token: "-",
+            front_back_separator_token: "-".into(),
+            front_back_separator_style_desc: Some(StyleDesc::PUNCTUATION),
             front_back_separator_count: 40,
+            layout: Layout::Lines,
+            truncation: Truncation::colored(indent),
+            style_overlay: StyleOverlay::INHERIT,
             item_format: TF::colored(indent),
         }
     }
@@ -63,17 +111,47 @@ impl<TF: Format> Format for VecDequeFormat<TF> {
             prefix_newlines: 0,
             intersperse_newlines: 1,
             suffix_newlines: 0,
-            front_back_separator_token: "-",
+            front_back_separator_token: "-".into(),
+            front_back_separator_style_desc: None,
             front_back_separator_count: 40,
+            layout: Layout::Lines,
+            truncation: Truncation::monochrome(indent),
+            style_overlay: StyleOverlay::INHERIT,
             item_format: TF::monochrome(indent),
         }
     }
 }
 
+impl<TF: Format> Default for VecDequeFormat<TF> {
+    fn default() -> Self {
+        Self::colored(0)
+    }
+}
+
+impl_collection_builders!(impl<TF> VecDequeFormat<TF>);
+
+impl<TF> CollectionFormat for VecDequeFormat<TF> {
+    fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    fn intersperse_newlines(&self) -> u16 {
+        self.intersperse_newlines
+    }
+
+    fn truncation(&self) -> &Truncation {
+        &self.truncation
+    }
+
+    fn style_overlay(&self) -> &StyleOverlay {
+        &self.style_overlay
+    }
+}
+
 
 #[cfg(test)]
 mod test {
-    use crate::{Color, ColorDisplay};
+    use crate::{layout::Limit, primitive::U8Format, Color, ColorDisplay};
     use super::*;
 
     #[test]
@@ -89,11 +167,50 @@ mod test {
             "    {}\n    {}\n{}\n    {}\n    {}",
             Color::Blue.bold().paint("10"),
             Color::Blue.bold().paint("20"),
-            "----------------------------------------",
+            Color::White.dimmed().paint("----------------------------------------"),
             Color::Blue.bold().paint("30"),
             Color::Blue.bold().paint("40"),
         );
         assert_eq!(sink, expected);
         Ok(())
     }
+
+    #[test]
+    fn custom_tokens_color_fmt() -> std::fmt::Result {
+        let vec_deque: VecDeque<u8> = VecDeque::from(vec![10, 20]);
+        let mut sink = String::with_capacity(1024);
+        let mut ctx = Context {
+            indent_token: Some("\t".into()),
+            newline_token: Some("\r\n".into()),
+            ..Context::default()
+        };
+        vec_deque.color_fmt_in(&mut sink, &VecDequeFormat {
+            front_back_separator_count: 3,
+            ..VecDequeFormat::<U8Format>::monochrome(1)
+        }, &mut ctx)?;
+        assert_eq!(sink, "\t10\r\n\t20\r\n---\r\n");
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_color_fmt() -> std::fmt::Result {
+        let mut vec_deque = VecDeque::new();
+        for n in (1..=3).rev() {
+            vec_deque.push_front(n);
+        }
+        for n in 4..=6 {
+            vec_deque.push_back(n);
+        }
+        let mut sink = String::with_capacity(1024);
+        vec_deque.color_fmt(&mut sink, &VecDequeFormat {
+            front_back_separator_count: 3,
+            truncation: Truncation {
+                limit: Some(Limit::HeadTail { head: 2, tail: 2 }),
+                ..Truncation::monochrome(0)
+            },
+            ..VecDequeFormat::<U8Format>::monochrome(0)
+        })?;
+        assert_eq!(sink, "1\n2\n… 2 more\n---\n5\n6");
+        Ok(())
+    }
 }
@@ -0,0 +1,154 @@
+//! Soft wrapping of text that may contain ANSI escape sequences.
+
+use crate::ansi::{self, Segment};
+use crate::Text;
+use std::ops::Range;
+
+/// Describes how to break text into lines that fit within a width.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Wrap {
+    /// The column at which lines are broken, e.g. the width of the terminal,
+    /// measured in display columns.
+    pub width: usize,
+    pub mode: WrapMode,
+    /// Written at the start of every line that a long line is broken into,
+    /// other than the first.  The marker counts towards the width.
+    pub marker: Text,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WrapMode {
+    /// Break lines at whitespace, whose whole run is dropped at the break.
+    /// Words that don't fit on a line by themselves are broken like
+    /// `WrapMode::Hard`.
+    Word,
+    /// Break lines at the last character that fits, regardless of words.
+    Hard,
+}
+
+impl Wrap {
+    /// Break `text` into lines of at most `width` display columns, where the
+    /// first line starts at column `start` and the continuation lines start
+    /// at `start` as well, i.e. `self.width - start` columns are available.
+    /// Lines are broken by inserting a `'\n'` followed by `self.marker`.
+    /// Newlines that are already part of `text` are kept, and the lines
+    /// that follow them don't start with the marker.
+    pub fn apply(&self, text: &str, start: usize) -> String {
+        let width = self.width.saturating_sub(start).max(1);
+        let marker_width = ansi::visible_width(&self.marker);
+        let mut wrapped = String::with_capacity(text.len() + 16);
+        // The visible width of the current line, and the width available.
+        let mut column = 0;
+        let mut available = width;
+        // The byte range in `wrapped` of the last run of whitespace on the
+        // current line, and the column just after it.
+        let mut last_space: Option<(Range<usize>, usize)> = None;
+        // Whether the line was just broken at whitespace, so that the rest of
+        // its run is dropped as well.
+        let mut skip_space = false;
+        for segment in ansi::segments(text) {
+            let text = match segment {
+                Segment::Escape(escape) => {
+                    wrapped.push_str(escape);
+                    continue;
+                },
+                Segment::Text(text) => text,
+            };
+            for c in text.chars() {
+                if c == '\n' {
+                    wrapped.push(c);
+                    column = 0;
+                    available = width;
+                    last_space = None;
+                    skip_space = false;
+                    continue;
+                }
+                if skip_space && c.is_whitespace() {
+                    continue;
+                }
+                skip_space = false;
+                let c_width = ansi::char_width(c);
+                if column + c_width > available && column > 0 {
+                    let is_space = c.is_whitespace();
+                    match last_space.take() {
+                        space if is_space && self.mode == WrapMode::Word => {
+                            // Break at `c`, and drop it along with its run.
+                            let run = space.map(|(run, _)| run)
+                                .filter(|run| run.end == wrapped.len());
+                            if let Some(run) = run {
+                                wrapped.truncate(run.start);
+                            }
+                            wrapped.push('\n');
+                            wrapped.push_str(&self.marker);
+                            column = marker_width;
+                            available = width.max(marker_width + 1);
+                            skip_space = true;
+                            continue;
+                        },
+                        Some((run, after)) if self.mode == WrapMode::Word => {
+                            let mut line_break = String::from("\n");
+                            line_break.push_str(&self.marker);
+                            wrapped.replace_range(run, &line_break);
+                            column = marker_width + column - after;
+                        },
+                        _ => {
+                            wrapped.push('\n');
+                            wrapped.push_str(&self.marker);
+                            column = marker_width;
+                        },
+                    }
+                    available = width.max(marker_width + 1);
+                }
+                if c.is_whitespace() {
+                    let start = match &last_space {
+                        Some((run, _)) if run.end == wrapped.len() => run.start,
+                        _ => wrapped.len(),
+                    };
+                    let end = wrapped.len() + c.len_utf8();
+                    last_space = Some((start..end, column + c_width));
+                }
+                wrapped.push(c);
+                column += c_width;
+            }
+        }
+        wrapped
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::Color;
+    use super::*;
+
+    #[test]
+    fn word_wrap() {
+        let wrap = Wrap { width: 12, mode: WrapMode::Word, marker: "".into() };
+        assert_eq!(
+            wrap.apply("the quick brown fox jumps", 2),
+            "the quick\nbrown fox\njumps",
+        );
+        assert_eq!(wrap.apply("abcdefghijklm no", 0), "abcdefghijkl\nm no");
+
+        let wrap = Wrap { marker: "> ".into(), ..wrap };
+        let text = format!("the {} brown fox", Color::Red.paint("quick"));
+        assert_eq!(
+            wrap.apply(&text, 0),
+            "the \x1b[31mquick\x1b[0m\n> brown fox",
+        );
+
+        // The whole run of whitespace is dropped at a break.
+        let wrap = Wrap { width: 10, marker: "".into(), ..wrap };
+        assert_eq!(wrap.apply("aaaa  bbbb  cccc", 0), "aaaa  bbbb\ncccc");
+        assert_eq!(wrap.apply("aaaa bbb  ccc", 1), "aaaa bbb\nccc");
+        assert_eq!(wrap.apply("aa  bbbbbbbb", 4), "aa\nbbbbbb\nbb");
+    }
+
+    #[test]
+    fn hard_wrap() {
+        let wrap = Wrap { width: 6, mode: WrapMode::Hard, marker: "".into() };
+        assert_eq!(wrap.apply("abc def\nghij", 1), "abc d\nef\nghij");
+        // Wide characters are never split across lines.
+        assert_eq!(wrap.apply("日本語です", 0), "日本語\nです");
+    }
+}